@@ -0,0 +1,6 @@
+ALTER TABLE "public"."place"
+DROP COLUMN "warning_percent";
+
+
+ALTER TABLE "public"."place"
+DROP COLUMN "alert_percent";
@@ -0,0 +1,4 @@
+ALTER TABLE "public"."place" ADD COLUMN "warning_percent" int8 DEFAULT NULL;
+
+
+ALTER TABLE "public"."place" ADD COLUMN "alert_percent" int8 DEFAULT NULL;
@@ -0,0 +1,4 @@
+DROP INDEX "public"."checkin_place_id_start_timestamp_index";
+
+
+DROP TABLE "public"."gauge_snapshot";
@@ -0,0 +1,16 @@
+CREATE TABLE "public"."gauge_snapshot" (
+    "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+    "place_id" uuid NOT NULL,
+    "gauge" int8 NOT NULL,
+    "created_at" timestamptz NOT NULL DEFAULT NOW(),
+    "updated_at" timestamptz NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("id")
+);
+
+CREATE INDEX "gauge_snapshot_place_id_created_at_index" ON "public"."gauge_snapshot" USING BTREE ("place_id", "created_at");
+
+ALTER TABLE "public"."gauge_snapshot" ADD FOREIGN KEY ("place_id") REFERENCES "public"."place" ("id") ON DELETE CASCADE;
+
+SELECT diesel_manage_updated_at('gauge_snapshot');
+
+CREATE INDEX "checkin_place_id_start_timestamp_index" ON "public"."checkin" USING BTREE ("place_id", "start_timestamp");
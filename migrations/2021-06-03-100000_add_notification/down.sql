@@ -0,0 +1,7 @@
+DROP TABLE "public"."notification";
+
+DROP TYPE notification_status;
+
+DROP TYPE notification_channel;
+
+DROP TYPE notification_kind;
@@ -0,0 +1,34 @@
+CREATE TYPE notification_kind AS ENUM ('infection_warning', 'infection_retraction');
+
+CREATE TYPE notification_channel AS ENUM ('email');
+
+CREATE TYPE notification_status AS ENUM ('pending', 'sent', 'failed');
+
+CREATE TABLE "public"."notification" (
+    "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+    "infection_id" uuid,
+    "checkin_id" uuid NOT NULL,
+    "user_id" uuid NOT NULL,
+    "kind" notification_kind NOT NULL,
+    "channel" notification_channel NOT NULL,
+    "status" notification_status NOT NULL DEFAULT 'pending',
+    "attempts" int8 NOT NULL DEFAULT 0,
+    "last_error" text,
+    "next_attempt_at" timestamptz NOT NULL DEFAULT NOW(),
+    "sent_at" timestamptz,
+    "created_at" timestamptz NOT NULL DEFAULT NOW(),
+    "updated_at" timestamptz NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("id")
+);
+
+CREATE INDEX "notification_status_next_attempt_at_index" ON "public"."notification" USING BTREE ("status", "next_attempt_at");
+
+CREATE INDEX "notification_infection_id_index" ON "public"."notification" USING BTREE ("infection_id");
+
+ALTER TABLE "public"."notification" ADD FOREIGN KEY ("infection_id") REFERENCES "public"."infection" ("id") ON DELETE SET NULL;
+
+ALTER TABLE "public"."notification" ADD FOREIGN KEY ("checkin_id") REFERENCES "public"."checkin" ("id") ON DELETE CASCADE;
+
+ALTER TABLE "public"."notification" ADD FOREIGN KEY ("user_id") REFERENCES "public"."user" ("id") ON DELETE CASCADE;
+
+SELECT diesel_manage_updated_at('notification');
@@ -0,0 +1,6 @@
+ALTER TABLE "public"."place"
+DROP COLUMN "minimum_overlap";
+
+
+ALTER TABLE "public"."notification"
+DROP COLUMN "overlap_minutes";
@@ -0,0 +1,4 @@
+ALTER TABLE "public"."place" ADD COLUMN "minimum_overlap" int8 DEFAULT NULL;
+
+
+ALTER TABLE "public"."notification" ADD COLUMN "overlap_minutes" int8 DEFAULT NULL;
@@ -0,0 +1,21 @@
+ALTER TABLE "public"."user"
+DROP COLUMN "region";
+
+
+DROP INDEX "place_region_index";
+
+ALTER TABLE "public"."place"
+DROP COLUMN "region";
+
+
+ALTER TABLE "public"."infection"
+DROP COLUMN "reviewed_at";
+
+ALTER TABLE "public"."infection"
+DROP COLUMN "reviewed_by";
+
+ALTER TABLE "public"."infection"
+DROP COLUMN "status";
+
+
+DROP TYPE infection_status;
@@ -0,0 +1,22 @@
+CREATE TYPE infection_status AS ENUM ('pending', 'approved', 'rejected');
+
+
+-- Infections declared before the review workflow are considered approved
+ALTER TABLE "public"."infection" ADD COLUMN "status" infection_status NOT NULL DEFAULT 'approved';
+
+ALTER TABLE "public"."infection" ALTER COLUMN "status" SET DEFAULT 'pending';
+
+
+ALTER TABLE "public"."infection" ADD COLUMN "reviewed_by" uuid DEFAULT NULL;
+
+ALTER TABLE "public"."infection" ADD COLUMN "reviewed_at" timestamptz DEFAULT NULL;
+
+ALTER TABLE "public"."infection" ADD FOREIGN KEY ("reviewed_by") REFERENCES "public"."user" ("id") ON DELETE SET NULL;
+
+
+ALTER TABLE "public"."place" ADD COLUMN "region" text DEFAULT NULL;
+
+CREATE INDEX "place_region_index" ON "public"."place" USING BTREE ("region");
+
+
+ALTER TABLE "public"."user" ADD COLUMN "region" text DEFAULT NULL;
@@ -0,0 +1,15 @@
+DELETE FROM "public"."notification" WHERE "kind" = 'infection_report';
+
+ALTER TYPE notification_kind RENAME TO notification_kind_old;
+
+CREATE TYPE notification_kind AS ENUM ('infection_warning', 'infection_retraction');
+
+ALTER TABLE "public"."notification" ALTER COLUMN "kind" TYPE notification_kind USING "kind"::text::notification_kind;
+
+DROP TYPE notification_kind_old;
+
+
+DROP INDEX "infection_reported_by_index";
+
+ALTER TABLE "public"."infection"
+DROP COLUMN "reported_by";
@@ -0,0 +1,14 @@
+ALTER TABLE "public"."infection" ADD COLUMN "reported_by" uuid DEFAULT NULL;
+
+ALTER TABLE "public"."infection" ADD FOREIGN KEY ("reported_by") REFERENCES "public"."user" ("id") ON DELETE SET NULL;
+
+CREATE INDEX "infection_reported_by_index" ON "public"."infection" USING BTREE ("reported_by");
+
+
+ALTER TYPE notification_kind RENAME TO notification_kind_old;
+
+CREATE TYPE notification_kind AS ENUM ('infection_warning', 'infection_retraction', 'infection_report');
+
+ALTER TABLE "public"."notification" ALTER COLUMN "kind" TYPE notification_kind USING "kind"::text::notification_kind;
+
+DROP TYPE notification_kind_old;
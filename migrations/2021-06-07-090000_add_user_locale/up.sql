@@ -0,0 +1 @@
+ALTER TABLE "public"."user" ADD COLUMN "locale" text NOT NULL DEFAULT 'fr';
@@ -0,0 +1,4 @@
+ALTER TABLE "public"."checkin"
+DROP COLUMN "reminded_at";
+
+DROP TABLE "public"."push_subscription";
@@ -0,0 +1,23 @@
+CREATE TABLE "public"."push_subscription" (
+    "id" uuid NOT NULL DEFAULT gen_random_uuid(),
+    "session_id" uuid NOT NULL,
+    "user_id" uuid NOT NULL,
+    "endpoint" text NOT NULL,
+    "p256dh" text NOT NULL,
+    "auth" text NOT NULL,
+    "created_at" timestamptz NOT NULL DEFAULT NOW(),
+    "updated_at" timestamptz NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("id")
+);
+
+CREATE UNIQUE INDEX "push_subscription_session_id_index" ON "public"."push_subscription" USING BTREE ("session_id");
+
+CREATE INDEX "push_subscription_user_id_index" ON "public"."push_subscription" USING BTREE ("user_id");
+
+ALTER TABLE "public"."push_subscription" ADD FOREIGN KEY ("session_id") REFERENCES "public"."session" ("id") ON DELETE CASCADE;
+
+ALTER TABLE "public"."push_subscription" ADD FOREIGN KEY ("user_id") REFERENCES "public"."user" ("id") ON DELETE CASCADE;
+
+SELECT diesel_manage_updated_at('push_subscription');
+
+ALTER TABLE "public"."checkin" ADD COLUMN "reminded_at" timestamptz DEFAULT NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE "public"."session"
+DROP COLUMN "last_used_at";
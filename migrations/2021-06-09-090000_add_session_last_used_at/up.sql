@@ -0,0 +1,3 @@
+ALTER TABLE "public"."session" ADD COLUMN "last_used_at" timestamptz DEFAULT NULL;
+
+UPDATE "public"."session" SET "last_used_at" = "updated_at" WHERE "confirmed" = TRUE;
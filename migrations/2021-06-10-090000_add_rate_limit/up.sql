@@ -0,0 +1,8 @@
+CREATE TABLE "public"."rate_limit" (
+    "key" text NOT NULL,
+    "window_start" timestamptz NOT NULL DEFAULT NOW(),
+    "hits" int4 NOT NULL DEFAULT 1,
+    PRIMARY KEY ("key")
+);
+
+CREATE INDEX "rate_limit_window_start_index" ON "public"."rate_limit" USING BTREE ("window_start");
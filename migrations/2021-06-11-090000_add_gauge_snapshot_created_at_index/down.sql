@@ -0,0 +1 @@
+DROP INDEX "public"."gauge_snapshot_created_at_index";
@@ -0,0 +1 @@
+CREATE INDEX "gauge_snapshot_created_at_index" ON "public"."gauge_snapshot" USING BTREE ("created_at");
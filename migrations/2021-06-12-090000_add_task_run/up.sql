@@ -0,0 +1,9 @@
+CREATE TABLE "public"."task_run" (
+    "lock_key" int8 NOT NULL,
+    "tick_timestamp" timestamptz NOT NULL,
+    "created_at" timestamptz NOT NULL DEFAULT NOW(),
+    "updated_at" timestamptz NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("lock_key")
+);
+
+SELECT diesel_manage_updated_at('task_run');
@@ -0,0 +1,11 @@
+DELETE FROM "public"."notification" WHERE "kind" = 'infection_summary';
+
+ALTER TYPE notification_kind RENAME TO notification_kind_old;
+
+CREATE TYPE notification_kind AS ENUM ('infection_warning', 'infection_retraction', 'infection_report');
+
+ALTER TABLE "public"."notification" ALTER COLUMN "kind" TYPE notification_kind USING "kind"::text::notification_kind;
+
+DROP TYPE notification_kind_old;
+
+ALTER TABLE "public"."notification" ALTER COLUMN "checkin_id" SET NOT NULL;
@@ -0,0 +1,9 @@
+ALTER TABLE "public"."notification" ALTER COLUMN "checkin_id" DROP NOT NULL;
+
+ALTER TYPE notification_kind RENAME TO notification_kind_old;
+
+CREATE TYPE notification_kind AS ENUM ('infection_warning', 'infection_retraction', 'infection_report', 'infection_summary');
+
+ALTER TABLE "public"."notification" ALTER COLUMN "kind" TYPE notification_kind USING "kind"::text::notification_kind;
+
+DROP TYPE notification_kind_old;
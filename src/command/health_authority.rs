@@ -0,0 +1,48 @@
+use crate::connector::ConnectorBuilder;
+use crate::model::user;
+use crate::security::{get_auth_from_email, get_legacy_login};
+
+#[derive(Clap, Debug)]
+pub struct HealthAuthorityFlags {
+    /// Email of the user to grant
+    email: String,
+
+    /// Region code of places reviewed by the user, all regions if omitted
+    #[clap(long)]
+    region: Option<String>,
+}
+
+pub async fn run(flags: HealthAuthorityFlags, builder: ConnectorBuilder) {
+    let connector = builder.create();
+
+    let (login, cleaned_email) = get_auth_from_email(
+        &connector.configuration.security.login_pepper,
+        flags.email.clone(),
+    );
+
+    // Rewrite a login derived before the pepper, otherwise the user would be created again
+    if let Err(error) =
+        user::upgrade_legacy_login(&connector, &get_legacy_login(&cleaned_email), &login)
+    {
+        error.exit();
+    }
+
+    let result = user::insert(
+        &connector,
+        &user::UserInsert {
+            login,
+            email: cleaned_email.clone(),
+            role: user::UserRole::HealthAuthority,
+        },
+        false,
+    )
+    .and_then(|user| {
+        user::update_role(&connector, user.id, user::UserRole::HealthAuthority)
+            .and_then(|_| user::set_region(&connector, user.id, flags.region.clone()))
+    });
+
+    match result {
+        Ok(_) => println!("{} granted as health authority", cleaned_email),
+        Err(error) => error.exit(),
+    }
+}
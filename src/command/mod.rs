@@ -1,8 +1,10 @@
+mod health_authority;
 mod serve;
 mod task;
 
 use crate::connector::ConnectorBuilder;
 use clap::Clap;
+use health_authority::HealthAuthorityFlags;
 use task::TaskFlags;
 
 /// Tackode backend CLI - Serve API or execute task
@@ -15,13 +17,17 @@ struct Opts {
 
 #[derive(Clap, Debug)]
 enum MainCommand {
-    /// Execute task like updating gauges - to be used with cron job
+    /// Execute task like updating gauges - to be used with cron job or the built-in scheduler
     #[clap(name = "task")]
     Task(TaskFlags),
 
     /// Serve API
     #[clap(name = "serve")]
     Serve,
+
+    /// Grant the health authority role to a user, reviewing infections of a region
+    #[clap(name = "health-authority")]
+    HealthAuthority(HealthAuthorityFlags),
 }
 
 pub async fn run(builder: ConnectorBuilder) {
@@ -30,5 +36,6 @@ pub async fn run(builder: ConnectorBuilder) {
     match opts.main_command {
         MainCommand::Task(task_flags) => task::run(task_flags, builder).await,
         MainCommand::Serve => serve::run(builder).await,
+        MainCommand::HealthAuthority(flags) => health_authority::run(flags, builder).await,
     }
 }
@@ -1,4 +1,7 @@
 use crate::connector::ConnectorBuilder;
+use crate::model::lock;
+use crate::task::TaskType;
+use std::process;
 
 #[derive(Clap, Debug)]
 pub struct TaskFlags {
@@ -7,23 +10,24 @@ pub struct TaskFlags {
     task_type: TaskType,
 }
 
-#[derive(Clap, Debug)]
-enum TaskType {
-    /// Refresh all gauges based on checkins
-    #[clap(name = "refresh-all-gauges")]
-    RefreshAllGauges,
-}
-
 pub async fn run(flags: TaskFlags, builder: ConnectorBuilder) {
     let connector = builder.create();
 
-    let task_result = match flags.task_type {
-        TaskType::RefreshAllGauges => {
-            crate::model::place::refresh_all_gauges(&connector).map(|_| ())
+    // Share the lock of the built-in scheduler to never overlap its runs
+    let lock = match lock::try_advisory_lock(&connector, flags.task_type.lock_key()) {
+        Ok(Some(lock)) => lock,
+        Ok(None) => {
+            eprintln!("Task {:?} is already running", flags.task_type);
+            process::exit(1);
         }
+        Err(error) => error.exit(),
     };
 
-    match task_result {
+    let result = flags.task_type.execute(&connector).await;
+
+    drop(lock);
+
+    match result {
         Ok(_) => println!("Task {:?} completed", flags.task_type),
         Err(error) => error.exit(),
     }
@@ -4,15 +4,98 @@ use std::env;
 pub struct GaugeConfiguration {
     pub alert: i64,
     pub warning: i64,
+    /// Days during which gauge snapshots are kept for occupancy analytics
+    pub snapshot_retention_days: i64,
+}
+
+#[derive(Clone)]
+pub struct OpeningHoursConfiguration {
+    /// Number of days for which opening hours are computed in advance
+    pub computed_days: i64,
+}
+
+#[derive(Clone)]
+pub struct SessionConfiguration {
+    /// Minutes during which a confirmation link is valid
+    pub confirmation_ttl: i64,
+    /// Days after which an unused session expires
+    pub idle_ttl: i64,
+    /// Days after which a session expires, even if used
+    pub absolute_ttl: i64,
+}
+
+#[derive(Clone)]
+pub struct SecurityConfiguration {
+    /// Secret key deriving logins from emails and hashing tokens, changing it requires
+    /// upgrading logins and signs every session out
+    pub login_pepper: String,
+    /// Secret key signing access tokens, disabled if None
+    pub access_token_secret: Option<String>,
+    /// Seconds during which an access token is valid
+    pub access_token_ttl: i64,
+}
+
+#[derive(Clone)]
+pub struct RateLimitConfiguration {
+    /// Seconds during which hits are counted
+    pub window: i64,
+    /// Maximum requests per IP address and endpoint within a window, 0 to disable
+    pub ip_requests: i64,
+    /// Maximum confirmation emails per login within a window, 0 to disable
+    pub login_requests: i64,
+    /// Use the last X-Forwarded-For address, set when behind a reverse proxy
+    pub trust_forwarded_for: bool,
+}
+
+#[derive(Clone)]
+pub struct CheckinConfiguration {
+    /// Minutes before the end of a checkin when its visitor is reminded
+    pub reminder_minutes: i64,
+}
+
+#[derive(Clone)]
+pub struct InfectionConfiguration {
+    /// Minutes of presence during an infection to be potentially infected
+    pub minimum_overlap: i64,
+    /// Days before the symptom onset from which a self-reported user is contagious
+    pub contagious_days_before: i64,
+    /// Days after the symptom onset until which a self-reported user is contagious
+    pub contagious_days_after: i64,
+}
+
+#[derive(Clone)]
+pub struct StatisticsConfiguration {
+    /// Minimum number of exposures for an aggregated group to be published
+    pub k_anonymity: i64,
+    /// Size in degrees of the geographic grid cells
+    pub grid_cell_size: f64,
+    /// Directory where CSV exports are written
+    pub export_directory: String,
+    /// Number of past days covered by CSV exports
+    pub export_days: i64,
 }
 
 pub struct Connector {
     pub gauge: GaugeConfiguration,
+    pub opening_hours: OpeningHoursConfiguration,
+    pub session: SessionConfiguration,
+    pub security: SecurityConfiguration,
+    pub rate_limit: RateLimitConfiguration,
+    pub checkin: CheckinConfiguration,
+    pub infection: InfectionConfiguration,
+    pub statistics: StatisticsConfiguration,
 }
 
 #[derive(Clone)]
 pub struct ConnectorBuilder {
     gauge: GaugeConfiguration,
+    opening_hours: OpeningHoursConfiguration,
+    session: SessionConfiguration,
+    security: SecurityConfiguration,
+    rate_limit: RateLimitConfiguration,
+    checkin: CheckinConfiguration,
+    infection: InfectionConfiguration,
+    statistics: StatisticsConfiguration,
 }
 
 impl ConnectorBuilder {
@@ -29,19 +112,207 @@ impl ConnectorBuilder {
             })
             .unwrap_or(80);
 
-        let builder = ConnectorBuilder {
+        let gauge_snapshot_retention_days: i64 = env::var("GAUGE_SNAPSHOT_RETENTION_DAYS")
+            .map(|retention_days_str| {
+                retention_days_str
+                    .parse::<i64>()
+                    .expect("Invalid GAUGE_SNAPSHOT_RETENTION_DAYS")
+            })
+            .unwrap_or(365);
+
+        let opening_hours_computed_days: i64 = env::var("OPENING_HOURS_COMPUTED_DAYS")
+            .map(|computed_days_str| {
+                computed_days_str
+                    .parse::<i64>()
+                    .expect("Invalid OPENING_HOURS_COMPUTED_DAYS")
+            })
+            .unwrap_or(14);
+
+        let session_confirmation_ttl: i64 = env::var("SESSION_CONFIRMATION_TTL_MINUTES")
+            .map(|ttl_str| {
+                ttl_str
+                    .parse::<i64>()
+                    .expect("Invalid SESSION_CONFIRMATION_TTL_MINUTES")
+            })
+            .unwrap_or(15);
+
+        let session_idle_ttl: i64 = env::var("SESSION_IDLE_TTL_DAYS")
+            .map(|ttl_str| {
+                ttl_str
+                    .parse::<i64>()
+                    .expect("Invalid SESSION_IDLE_TTL_DAYS")
+            })
+            .unwrap_or(30);
+
+        let session_absolute_ttl: i64 = env::var("SESSION_ABSOLUTE_TTL_DAYS")
+            .map(|ttl_str| {
+                ttl_str
+                    .parse::<i64>()
+                    .expect("Invalid SESSION_ABSOLUTE_TTL_DAYS")
+            })
+            .unwrap_or(365);
+
+        let login_pepper = env::var("LOGIN_PEPPER").expect("LOGIN_PEPPER must be set");
+
+        if login_pepper.is_empty() {
+            panic!("LOGIN_PEPPER must not be empty");
+        }
+
+        let access_token_secret = env::var("ACCESS_TOKEN_SECRET")
+            .ok()
+            .filter(|secret| !secret.is_empty());
+
+        let access_token_ttl: i64 = env::var("ACCESS_TOKEN_TTL_SECONDS")
+            .map(|ttl_str| {
+                ttl_str
+                    .parse::<i64>()
+                    .expect("Invalid ACCESS_TOKEN_TTL_SECONDS")
+            })
+            .unwrap_or(900);
+
+        let rate_limit_window: i64 = env::var("RATE_LIMIT_WINDOW_SECONDS")
+            .map(|window_str| {
+                window_str
+                    .parse::<i64>()
+                    .expect("Invalid RATE_LIMIT_WINDOW_SECONDS")
+            })
+            .unwrap_or(240);
+
+        let rate_limit_ip_requests: i64 = env::var("RATE_LIMIT_IP_REQUESTS")
+            .map(|requests_str| {
+                requests_str
+                    .parse::<i64>()
+                    .expect("Invalid RATE_LIMIT_IP_REQUESTS")
+            })
+            .unwrap_or(30);
+
+        let rate_limit_login_requests: i64 = env::var("RATE_LIMIT_LOGIN_REQUESTS")
+            .map(|requests_str| {
+                requests_str
+                    .parse::<i64>()
+                    .expect("Invalid RATE_LIMIT_LOGIN_REQUESTS")
+            })
+            .unwrap_or(3);
+
+        let rate_limit_trust_forwarded_for: bool = env::var("RATE_LIMIT_TRUST_FORWARDED_FOR")
+            .map(|trust_str| {
+                trust_str
+                    .parse::<bool>()
+                    .expect("Invalid RATE_LIMIT_TRUST_FORWARDED_FOR")
+            })
+            .unwrap_or(false);
+
+        let checkin_reminder_minutes: i64 = env::var("CHECKIN_REMINDER_MINUTES")
+            .map(|reminder_minutes_str| {
+                reminder_minutes_str
+                    .parse::<i64>()
+                    .expect("Invalid CHECKIN_REMINDER_MINUTES")
+            })
+            .unwrap_or(10);
+
+        let infection_minimum_overlap: i64 = env::var("INFECTION_MINIMUM_OVERLAP")
+            .map(|minimum_overlap_str| {
+                minimum_overlap_str
+                    .parse::<i64>()
+                    .expect("Invalid INFECTION_MINIMUM_OVERLAP")
+            })
+            .unwrap_or(15);
+
+        let infection_contagious_days_before: i64 = env::var("INFECTION_CONTAGIOUS_DAYS_BEFORE")
+            .map(|days_str| {
+                days_str
+                    .parse::<i64>()
+                    .expect("Invalid INFECTION_CONTAGIOUS_DAYS_BEFORE")
+            })
+            .unwrap_or(2);
+
+        let infection_contagious_days_after: i64 = env::var("INFECTION_CONTAGIOUS_DAYS_AFTER")
+            .map(|days_str| {
+                days_str
+                    .parse::<i64>()
+                    .expect("Invalid INFECTION_CONTAGIOUS_DAYS_AFTER")
+            })
+            .unwrap_or(10);
+
+        let statistics_k_anonymity: i64 = env::var("STATISTICS_K_ANONYMITY")
+            .map(|k_anonymity_str| {
+                k_anonymity_str
+                    .parse::<i64>()
+                    .expect("Invalid STATISTICS_K_ANONYMITY")
+            })
+            .unwrap_or(5);
+
+        let statistics_grid_cell_size: f64 = env::var("STATISTICS_GRID_CELL_SIZE")
+            .map(|grid_cell_size_str| {
+                grid_cell_size_str
+                    .parse::<f64>()
+                    .expect("Invalid STATISTICS_GRID_CELL_SIZE")
+            })
+            .unwrap_or(0.01);
+
+        let statistics_export_directory: String =
+            env::var("STATISTICS_EXPORT_DIRECTORY").unwrap_or_else(|_| String::from("."));
+
+        let statistics_export_days: i64 = env::var("STATISTICS_EXPORT_DAYS")
+            .map(|export_days_str| {
+                export_days_str
+                    .parse::<i64>()
+                    .expect("Invalid STATISTICS_EXPORT_DAYS")
+            })
+            .unwrap_or(90);
+
+        ConnectorBuilder {
             gauge: GaugeConfiguration {
                 alert: alert_gauge,
                 warning: warning_gauge,
+                snapshot_retention_days: gauge_snapshot_retention_days,
             },
-        };
-
-        builder
+            opening_hours: OpeningHoursConfiguration {
+                computed_days: opening_hours_computed_days,
+            },
+            session: SessionConfiguration {
+                confirmation_ttl: session_confirmation_ttl,
+                idle_ttl: session_idle_ttl,
+                absolute_ttl: session_absolute_ttl,
+            },
+            security: SecurityConfiguration {
+                login_pepper,
+                access_token_secret,
+                access_token_ttl,
+            },
+            rate_limit: RateLimitConfiguration {
+                window: rate_limit_window,
+                ip_requests: rate_limit_ip_requests,
+                login_requests: rate_limit_login_requests,
+                trust_forwarded_for: rate_limit_trust_forwarded_for,
+            },
+            checkin: CheckinConfiguration {
+                reminder_minutes: checkin_reminder_minutes,
+            },
+            infection: InfectionConfiguration {
+                minimum_overlap: infection_minimum_overlap,
+                contagious_days_before: infection_contagious_days_before,
+                contagious_days_after: infection_contagious_days_after,
+            },
+            statistics: StatisticsConfiguration {
+                k_anonymity: statistics_k_anonymity,
+                grid_cell_size: statistics_grid_cell_size,
+                export_directory: statistics_export_directory,
+                export_days: statistics_export_days,
+            },
+        }
     }
 
     pub fn create(&self) -> Connector {
         Connector {
             gauge: self.gauge.clone(),
+            opening_hours: self.opening_hours.clone(),
+            session: self.session.clone(),
+            security: self.security.clone(),
+            rate_limit: self.rate_limit.clone(),
+            checkin: self.checkin.clone(),
+            infection: self.infection.clone(),
+            statistics: self.statistics.clone(),
         }
     }
 }
@@ -1,5 +1,6 @@
 pub mod template;
 
+use custom_error::custom_error;
 use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::transport::smtp::extension::ClientId;
@@ -8,6 +9,12 @@ use std::{env, str::FromStr};
 use template::{EmailData, TemplateStorage};
 use tracing::error;
 
+custom_error! { pub Error
+    Compilation{source: template::Error} = "Error while compiling email: {source}",
+    Building{source: lettre::error::Error} = "Error while building email: {source}",
+    Transport{source: lettre::transport::smtp::Error} = "Error while sending email: {source}",
+}
+
 pub struct Connector {
     smtp_transport: AsyncSmtpTransport<Tokio1Executor>,
     smtp_domain: String,
@@ -19,45 +26,42 @@ impl Connector {
     // Use EmailData to instanciate an email
     pub async fn send(&self, data: Vec<impl EmailData>) {
         for data in data.iter() {
-            match data.compile_with(&self.template_storage) {
-                Ok(email) => {
-                    let message_id = format!("<{}@{}>", uuid::Uuid::new_v4(), self.smtp_domain);
-
-                    let html_part =
-                        MultiPart::related().singlepart(SinglePart::html(email.html.clone()));
-
-                    // Handle embeds
-                    let html_part = email.embeds.iter().fold(html_part, |html_part, embed| {
-                        html_part.singlepart(
-                            Attachment::new_inline(embed.content_id.clone())
-                                .body(embed.body.clone(), embed.content_type.clone()),
-                        )
-                    });
-
-                    let message = Message::builder()
-                        .from(self.from.clone())
-                        .to(Mailbox::new(None, email.to))
-                        .subject(email.subject.clone())
-                        .message_id(Some(message_id))
-                        .multipart(
-                            MultiPart::alternative()
-                                .singlepart(SinglePart::plain(email.text))
-                                .multipart(html_part),
-                        );
-
-                    match message {
-                        Ok(message) => match self.smtp_transport.send(message).await {
-                            Ok(_) => (),
-                            Err(error) => error!("Error while sending email: {}", error),
-                        },
-                        Err(error) => error!("Error while building email: {}", error),
-                    }
-                }
-
-                Err(error) => error!("Error while compiling email: {}", error),
+            if let Err(error) = self.send_one(data).await {
+                error!("{}", error);
             }
         }
     }
+
+    // Send a single email, reporting failures to the caller
+    pub async fn send_one(&self, data: &impl EmailData) -> Result<(), Error> {
+        let email = data.compile_with(&self.template_storage)?;
+        let message_id = format!("<{}@{}>", uuid::Uuid::new_v4(), self.smtp_domain);
+
+        let html_part = MultiPart::related().singlepart(SinglePart::html(email.html.clone()));
+
+        // Handle embeds
+        let html_part = email.embeds.iter().fold(html_part, |html_part, embed| {
+            html_part.singlepart(
+                Attachment::new_inline(embed.content_id.clone())
+                    .body(embed.body.clone(), embed.content_type.clone()),
+            )
+        });
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(Mailbox::new(None, email.to))
+            .subject(email.subject.clone())
+            .message_id(Some(message_id))
+            .multipart(
+                MultiPart::alternative()
+                    .singlepart(SinglePart::plain(email.text))
+                    .multipart(html_part),
+            )?;
+
+        self.smtp_transport.send(message).await?;
+
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
@@ -1,12 +1,13 @@
 use super::{
-    precompile_template, EmailData, EmailTemplate, PrecompiledTemplate, TemplateData,
-    TemplateStorage,
+    precompile_template, EmailData, EmailTemplate, Locale, LocalizedTemplate, PrecompiledTemplate,
+    TemplateData, TemplateStorage,
 };
 use std::collections::HashMap;
 
 pub struct DeviceValidationEmail {
     pub to: String,
     pub url: String,
+    pub locale: Locale,
 }
 
 impl EmailData for DeviceValidationEmail {
@@ -14,6 +15,10 @@ impl EmailData for DeviceValidationEmail {
         self.to.clone()
     }
 
+    fn locale(&self) -> Locale {
+        self.locale
+    }
+
     fn template_from_storage(storage: &TemplateStorage) -> &dyn EmailTemplate {
         &storage.device_validation
     }
@@ -28,7 +33,7 @@ impl EmailData for DeviceValidationEmail {
 
 #[derive(Clone)]
 pub struct DeviceValidationTemplate {
-    precompiled: PrecompiledTemplate,
+    precompiled: LocalizedTemplate,
 }
 
 impl DeviceValidationTemplate {
@@ -36,7 +41,10 @@ impl DeviceValidationTemplate {
         DeviceValidationTemplate {
             precompiled: precompile_template(TemplateData {
                 name: "deviceValidation",
-                subject: "Validation de la session",
+                subjects: vec![
+                    (Locale::Fr, "Validation de la session"),
+                    (Locale::En, "Session validation"),
+                ],
                 utf8_subject: false,
                 embeds: vec![(
                     "../assets/logo.png",
@@ -48,7 +56,7 @@ impl DeviceValidationTemplate {
 }
 
 impl EmailTemplate for DeviceValidationTemplate {
-    fn precompiled(&self) -> &PrecompiledTemplate {
-        &self.precompiled
+    fn precompiled(&self, locale: Locale) -> &PrecompiledTemplate {
+        self.precompiled.get(locale)
     }
 }
@@ -0,0 +1,73 @@
+use super::{
+    precompile_template, EmailData, EmailTemplate, Locale, LocalizedTemplate, PrecompiledTemplate,
+    TemplateData, TemplateStorage,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+pub struct InfectionReportEmail {
+    pub to: String,
+    pub place_name: String,
+    /// Only the day is rendered, not to disclose the visit of the reporter
+    pub checkin_date: DateTime<Utc>,
+    /// Timezone of the place
+    pub timezone: Tz,
+    pub locale: Locale,
+}
+
+impl EmailData for InfectionReportEmail {
+    fn to(&self) -> String {
+        self.to.clone()
+    }
+
+    fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    fn template_from_storage(storage: &TemplateStorage) -> &dyn EmailTemplate {
+        &storage.infection_report
+    }
+
+    fn into(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        map.insert("placeName".to_string(), self.place_name.clone());
+
+        map.insert(
+            "checkinDate".to_string(),
+            self.locale.format_date(&self.checkin_date, &self.timezone),
+        );
+
+        map
+    }
+}
+
+#[derive(Clone)]
+pub struct InfectionReportTemplate {
+    precompiled: LocalizedTemplate,
+}
+
+impl InfectionReportTemplate {
+    pub fn new() -> Self {
+        InfectionReportTemplate {
+            precompiled: precompile_template(TemplateData {
+                name: "infectionReport",
+                subjects: vec![
+                    (Locale::Fr, "Déclaration d'un test positif par un visiteur"),
+                    (Locale::En, "Positive test reported by a visitor"),
+                ],
+                utf8_subject: true,
+                embeds: vec![(
+                    "../assets/logo.png",
+                    "image/png".parse().expect("Unable to parse ContentType"),
+                )],
+            }),
+        }
+    }
+}
+
+impl EmailTemplate for InfectionReportTemplate {
+    fn precompiled(&self, locale: Locale) -> &PrecompiledTemplate {
+        self.precompiled.get(locale)
+    }
+}
@@ -0,0 +1,78 @@
+use super::{
+    precompile_template, EmailData, EmailTemplate, Locale, LocalizedTemplate, PrecompiledTemplate,
+    TemplateData, TemplateStorage,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+pub struct InfectionRetractionEmail {
+    pub to: String,
+    pub organization_name: String,
+    pub place_name: String,
+    pub checkin_datetime: DateTime<Utc>,
+    /// Timezone of the place
+    pub timezone: Tz,
+    pub locale: Locale,
+}
+
+impl EmailData for InfectionRetractionEmail {
+    fn to(&self) -> String {
+        self.to.clone()
+    }
+
+    fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    fn template_from_storage(storage: &TemplateStorage) -> &dyn EmailTemplate {
+        &storage.infection_retraction
+    }
+
+    fn into(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        map.insert(
+            "organizationName".to_string(),
+            self.organization_name.clone(),
+        );
+        map.insert("placeName".to_string(), self.place_name.clone());
+
+        map.insert(
+            "checkinDateTime".to_string(),
+            self.locale
+                .format_datetime(&self.checkin_datetime, &self.timezone),
+        );
+
+        map
+    }
+}
+
+#[derive(Clone)]
+pub struct InfectionRetractionTemplate {
+    precompiled: LocalizedTemplate,
+}
+
+impl InfectionRetractionTemplate {
+    pub fn new() -> Self {
+        InfectionRetractionTemplate {
+            precompiled: precompile_template(TemplateData {
+                name: "infectionRetraction",
+                subjects: vec![
+                    (Locale::Fr, "Annulation d'une alerte de contact potentiel"),
+                    (Locale::En, "Potential contact alert cancelled"),
+                ],
+                utf8_subject: true,
+                embeds: vec![(
+                    "../assets/logo.png",
+                    "image/png".parse().expect("Unable to parse ContentType"),
+                )],
+            }),
+        }
+    }
+}
+
+impl EmailTemplate for InfectionRetractionTemplate {
+    fn precompiled(&self, locale: Locale) -> &PrecompiledTemplate {
+        self.precompiled.get(locale)
+    }
+}
@@ -0,0 +1,118 @@
+use super::{
+    escape_html, precompile_template, EmailData, EmailTemplate, Locale, LocalizedTemplate,
+    PrecompiledTemplate, TemplateData, TemplateStorage,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+/// Outcome of an approved infection sent to the organization owner
+pub struct InfectionSummaryEmail {
+    pub to: String,
+    pub start_datetime: DateTime<Utc>,
+    pub end_datetime: DateTime<Utc>,
+    /// Timezone of the first place, by name
+    pub timezone: Tz,
+    pub places: Vec<InfectionSummaryPlace>,
+    pub locale: Locale,
+}
+
+pub struct InfectionSummaryPlace {
+    pub place_name: String,
+    pub visitors_count: i64,
+}
+
+impl EmailData for InfectionSummaryEmail {
+    fn to(&self) -> String {
+        self.to.clone()
+    }
+
+    fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    fn template_from_storage(storage: &TemplateStorage) -> &dyn EmailTemplate {
+        &storage.infection_summary
+    }
+
+    fn into(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        map.insert(
+            "startDateTime".to_string(),
+            self.locale
+                .format_datetime(&self.start_datetime, &self.timezone),
+        );
+        map.insert(
+            "endDateTime".to_string(),
+            self.locale
+                .format_datetime(&self.end_datetime, &self.timezone),
+        );
+
+        let places_text: Vec<String> = self
+            .places
+            .iter()
+            .map(|place| {
+                format!(
+                    "**{}**\n{}",
+                    place.place_name,
+                    place.description(self.locale)
+                )
+            })
+            .collect();
+        map.insert("placesText".to_string(), places_text.join("\n\n"));
+
+        let places_html: Vec<String> = self
+            .places
+            .iter()
+            .map(|place| {
+                format!(
+                    "<p><strong>{}</strong><br />{}</p>",
+                    escape_html(&place.place_name),
+                    place.description(self.locale)
+                )
+            })
+            .collect();
+        map.insert("placesHtml".to_string(), places_html.join(""));
+
+        map
+    }
+}
+
+impl InfectionSummaryPlace {
+    fn description(&self, locale: Locale) -> String {
+        match locale {
+            Locale::Fr => format!("{} visiteur(s) concerné(s)", self.visitors_count),
+            Locale::En => format!("{} visitor(s) concerned", self.visitors_count),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct InfectionSummaryTemplate {
+    precompiled: LocalizedTemplate,
+}
+
+impl InfectionSummaryTemplate {
+    pub fn new() -> Self {
+        InfectionSummaryTemplate {
+            precompiled: precompile_template(TemplateData {
+                name: "infectionSummary",
+                subjects: vec![
+                    (Locale::Fr, "Résultat de la déclaration d'infection"),
+                    (Locale::En, "Outcome of the infection declaration"),
+                ],
+                utf8_subject: true,
+                embeds: vec![(
+                    "../assets/logo.png",
+                    "image/png".parse().expect("Unable to parse ContentType"),
+                )],
+            }),
+        }
+    }
+}
+
+impl EmailTemplate for InfectionSummaryTemplate {
+    fn precompiled(&self, locale: Locale) -> &PrecompiledTemplate {
+        self.precompiled.get(locale)
+    }
+}
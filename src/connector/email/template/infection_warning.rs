@@ -1,16 +1,25 @@
 use super::{
-    precompile_template, EmailData, EmailTemplate, PrecompiledTemplate, TemplateData,
-    TemplateStorage,
+    escape_html, precompile_template, EmailData, EmailTemplate, Locale, LocalizedTemplate,
+    PrecompiledTemplate, TemplateData, TemplateStorage,
 };
 use chrono::{DateTime, Utc};
-use chrono_tz::Europe::Paris;
+use chrono_tz::Tz;
 use std::collections::HashMap;
 
+/// Consolidated warning of all exposures of a visitor
 pub struct InfectionWarningEmail {
     pub to: String,
+    pub exposures: Vec<InfectionWarningExposure>,
+    pub locale: Locale,
+}
+
+pub struct InfectionWarningExposure {
     pub organization_name: String,
     pub place_name: String,
     pub checkin_datetime: DateTime<Utc>,
+    /// Timezone of the place
+    pub timezone: Tz,
+    pub overlap_minutes: i64,
 }
 
 impl EmailData for InfectionWarningEmail {
@@ -18,31 +27,69 @@ impl EmailData for InfectionWarningEmail {
         self.to.clone()
     }
 
+    fn locale(&self) -> Locale {
+        self.locale
+    }
+
     fn template_from_storage(storage: &TemplateStorage) -> &dyn EmailTemplate {
         &storage.infection_warning
     }
 
     fn into(&self) -> HashMap<String, String> {
         let mut map = HashMap::new();
-        map.insert(
-            "organizationName".to_string(),
-            self.organization_name.clone(),
-        );
-        map.insert("placeName".to_string(), self.place_name.clone());
 
-        let paris_time = self.checkin_datetime.with_timezone(&Paris);
-        map.insert(
-            "checkinDateTime".to_string(),
-            paris_time.format("%d/%m/%y à %Hh%M").to_string(),
-        );
+        let exposures_text: Vec<String> = self
+            .exposures
+            .iter()
+            .map(|exposure| {
+                format!(
+                    "**{}**\n**{}**\n{}",
+                    exposure.organization_name,
+                    exposure.place_name,
+                    exposure.description(self.locale)
+                )
+            })
+            .collect();
+        map.insert("exposuresText".to_string(), exposures_text.join("\n\n"));
+
+        let exposures_html: Vec<String> = self
+            .exposures
+            .iter()
+            .map(|exposure| {
+                format!(
+                    "<p><strong>{}</strong><br /><strong>{}</strong><br />{}</p>",
+                    escape_html(&exposure.organization_name),
+                    escape_html(&exposure.place_name),
+                    exposure.description(self.locale)
+                )
+            })
+            .collect();
+        map.insert("exposuresHtml".to_string(), exposures_html.join(""));
 
         map
     }
 }
 
+impl InfectionWarningExposure {
+    fn description(&self, locale: Locale) -> String {
+        let datetime = locale.format_datetime(&self.checkin_datetime, &self.timezone);
+
+        match locale {
+            Locale::Fr => format!(
+                "le {}, présence d'environ {} minutes pendant la période d'exposition",
+                datetime, self.overlap_minutes
+            ),
+            Locale::En => format!(
+                "on {}, about {} minutes of presence during the exposure period",
+                datetime, self.overlap_minutes
+            ),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct InfectionWarningTemplate {
-    precompiled: PrecompiledTemplate,
+    precompiled: LocalizedTemplate,
 }
 
 impl InfectionWarningTemplate {
@@ -50,7 +97,13 @@ impl InfectionWarningTemplate {
         InfectionWarningTemplate {
             precompiled: precompile_template(TemplateData {
                 name: "infectionWarning",
-                subject: "⚠️ Contact potentiel avec une personne infectée",
+                subjects: vec![
+                    (
+                        Locale::Fr,
+                        "⚠️ Contact potentiel avec une personne infectée",
+                    ),
+                    (Locale::En, "⚠️ Potential contact with an infected person"),
+                ],
                 utf8_subject: true,
                 embeds: vec![(
                     "../assets/logo.png",
@@ -62,7 +115,7 @@ impl InfectionWarningTemplate {
 }
 
 impl EmailTemplate for InfectionWarningTemplate {
-    fn precompiled(&self) -> &PrecompiledTemplate {
-        &self.precompiled
+    fn precompiled(&self, locale: Locale) -> &PrecompiledTemplate {
+        self.precompiled.get(locale)
     }
 }
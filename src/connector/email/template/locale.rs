@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+/// Language of the emails, French being used when a template is not translated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    Fr,
+    En,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::Fr, Locale::En];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::Fr => "fr",
+            Locale::En => "en",
+        }
+    }
+
+    /// Parse a language tag like `en-GB`, only its primary language being used
+    pub fn from_code(code: &str) -> Option<Locale> {
+        let language = code.trim().split('-').next()?.to_lowercase();
+
+        Locale::ALL
+            .iter()
+            .find(|locale| locale.as_str() == language)
+            .copied()
+    }
+
+    /// Select the supported language with the highest quality of an `Accept-Language` header
+    pub fn from_accept_language(header: &str) -> Option<Locale> {
+        let mut languages: Vec<(Locale, f32)> = header
+            .split(',')
+            .filter_map(|language| {
+                let mut parts = language.split(';');
+                let locale = Locale::from_code(parts.next()?)?;
+                let quality = parts
+                    .find_map(|part| part.trim().strip_prefix("q="))
+                    .map(|q| q.parse::<f32>().unwrap_or(0.0))
+                    .unwrap_or(1.0);
+
+                Some((locale, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+
+        // Stable sort keeps the header order between equal qualities
+        languages.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        languages.first().map(|(locale, _)| *locale)
+    }
+
+    /// Format a timestamp in the timezone of the place
+    pub fn format_datetime(&self, datetime: &DateTime<Utc>, timezone: &Tz) -> String {
+        let local_datetime = datetime.with_timezone(timezone);
+
+        match self {
+            Locale::Fr => local_datetime.format("%d/%m/%y à %Hh%M").to_string(),
+            Locale::En => local_datetime.format("%b %-d, %Y at %H:%M").to_string(),
+        }
+    }
+
+    /// Format the day of a timestamp in the timezone of the place
+    pub fn format_date(&self, datetime: &DateTime<Utc>, timezone: &Tz) -> String {
+        let local_datetime = datetime.with_timezone(timezone);
+
+        match self {
+            Locale::Fr => local_datetime.format("%d/%m/%y").to_string(),
+            Locale::En => local_datetime.format("%b %-d, %Y").to_string(),
+        }
+    }
+
+    /// Name of the template file, untranslated for French
+    pub fn file_name(&self, name: &str, extension: &str) -> String {
+        match self {
+            Locale::Fr => format!("{}.{}", name, extension),
+            _ => format!("{}.{}.{}", name, self.as_str(), extension),
+        }
+    }
+}
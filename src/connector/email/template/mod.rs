@@ -1,5 +1,9 @@
 mod device_validation;
+mod infection_report;
+mod infection_retraction;
+mod infection_summary;
 mod infection_warning;
+mod locale;
 mod storage;
 
 use custom_error::custom_error;
@@ -11,7 +15,11 @@ use std::path::Path;
 use std::str::FromStr;
 
 pub use device_validation::DeviceValidationEmail;
-pub use infection_warning::InfectionWarningEmail;
+pub use infection_report::InfectionReportEmail;
+pub use infection_retraction::InfectionRetractionEmail;
+pub use infection_summary::{InfectionSummaryEmail, InfectionSummaryPlace};
+pub use infection_warning::{InfectionWarningEmail, InfectionWarningExposure};
+pub use locale::Locale;
 pub use storage::TemplateStorage;
 
 custom_error! { pub Error
@@ -32,14 +40,14 @@ struct Asset;
 
 pub struct TemplateData {
     pub name: &'static str,
-    pub subject: &'static str,
+    /// Subject of each translation, French being required
+    pub subjects: Vec<(Locale, &'static str)>,
     pub utf8_subject: bool,
     pub embeds: Vec<(&'static str, ContentType)>,
 }
 
 #[derive(Clone)]
 pub struct PrecompiledTemplate {
-    name: String,
     html: String,
     text: String,
     subject: String,
@@ -47,16 +55,31 @@ pub struct PrecompiledTemplate {
     embeds: Vec<Embed>,
 }
 
+/// Precompiled translations of a template
+#[derive(Clone)]
+pub struct LocalizedTemplate {
+    templates: HashMap<Locale, PrecompiledTemplate>,
+}
+
+impl LocalizedTemplate {
+    /// Get the translation, falling back on French
+    pub fn get(&self, locale: Locale) -> &PrecompiledTemplate {
+        self.templates
+            .get(&locale)
+            .or_else(|| self.templates.get(&Locale::default()))
+            .expect("Default template not found")
+    }
+}
+
 #[derive(Clone)]
 pub struct Embed {
     pub body: Vec<u8>,
-    pub filename: String,
     pub content_type: ContentType,
     pub content_id: String,
 }
 
 pub trait EmailTemplate {
-    fn precompiled(&self) -> &PrecompiledTemplate;
+    fn precompiled(&self, locale: Locale) -> &PrecompiledTemplate;
 }
 
 pub struct CompiledEmail {
@@ -67,37 +90,63 @@ pub struct CompiledEmail {
     pub embeds: Vec<Embed>,
 }
 
-fn precompile_template(data: TemplateData) -> PrecompiledTemplate {
-    // Expect template html and txt
-    let mut html = html(format!("{}.html", data.name))
-        .expect(&format!("{} HTML template not found", data.name));
+fn precompile_template(data: TemplateData) -> LocalizedTemplate {
+    let mut templates = HashMap::new();
 
-    let text = text(format!("{}.txt", data.name))
-        .expect(&format!("{} Text template not found", data.name));
+    for locale in Locale::ALL.iter() {
+        // Expect template html and txt, only the default one being mandatory
+        let html = match html(locale.file_name(data.name, "html")) {
+            Some(html) => html,
+            None if *locale == Locale::default() => {
+                panic!("{} HTML template not found", data.name)
+            }
+            None => continue,
+        };
 
-    // Prepare and replace embeds
-    let embeds =
-        data.embeds
+        let text = text(locale.file_name(data.name, "txt"))
+            .unwrap_or_else(|| panic!("{} Text template not found ({:?})", data.name, locale));
+
+        let subject = data
+            .subjects
             .iter()
-            .map(|(filepath, content_type)| {
-                let (new_html, embed) =
-                    embed_in_template(html.clone(), filepath, content_type.clone()).expect(
-                        &format!("{} Embed not found in template {}", filepath, data.name),
-                    );
+            .find(|(subject_locale, _)| subject_locale == locale)
+            .map(|(_, subject)| *subject)
+            .unwrap_or_else(|| panic!("{} Subject not found ({:?})", data.name, locale));
 
-                // Assign prepared HTML
-                html = new_html;
+        templates.insert(*locale, precompile_locale(&data, html, text, subject));
+    }
 
-                // Return prepared embed
-                embed
-            })
-            .collect();
+    LocalizedTemplate { templates }
+}
+
+fn precompile_locale(
+    data: &TemplateData,
+    mut html: String,
+    text: String,
+    subject: &str,
+) -> PrecompiledTemplate {
+    // Prepare and replace embeds
+    let embeds = data
+        .embeds
+        .iter()
+        .map(|(filepath, content_type)| {
+            let (new_html, embed) = embed_in_template(html.clone(), filepath, content_type.clone())
+                .unwrap_or_else(|| {
+                    panic!("{} Embed not found in template {}", filepath, data.name)
+                });
+
+            // Assign prepared HTML
+            html = new_html;
+
+            // Return prepared embed
+            embed
+        })
+        .collect();
 
     PrecompiledTemplate {
-        name: data.name.to_string(),
-        html: html.clone(),
-        text: text.clone(),
-        subject: data.subject.to_string(),
+        html,
+        text,
+        subject: subject.to_string(),
         utf8_subject: data.utf8_subject,
         embeds,
     }
@@ -105,6 +154,7 @@ fn precompile_template(data: TemplateData) -> PrecompiledTemplate {
 
 pub trait EmailData {
     fn to(&self) -> String;
+    fn locale(&self) -> Locale;
     fn template_from_storage(storage: &TemplateStorage) -> &dyn EmailTemplate;
     fn into(&self) -> HashMap<String, String>;
 
@@ -116,7 +166,7 @@ pub trait EmailData {
         );
 
         Self::template_from_storage(storage)
-            .precompiled()
+            .precompiled(self.locale())
             .compile(self.to(), data)
     }
 }
@@ -152,6 +202,14 @@ impl PrecompiledTemplate {
     }
 }
 
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 fn html(filename: String) -> Option<String> {
     Html::get(&filename).and_then(|template| String::from_utf8(template.into()).ok())
 }
@@ -172,7 +230,7 @@ fn embed_in_template(
         .and_then(|(embed_filepath, filename)| // Load file
             Asset::get(&embed_filepath).map(|body| {
                 // Replace references in template by content id
-                let content_id = filename.clone(); // format!("{}@tackode.com", filename);
+                let content_id = filename; // format!("{}@tackode.com", filename);
                 let content_id_tmpl = format!("cid:{}", content_id);
                 let new_template = template.replace(filepath, &content_id_tmpl);
 
@@ -180,9 +238,8 @@ fn embed_in_template(
                     new_template,
                     Embed {
                         body: body.into(),
-                        filename,
                         content_type,
-                        content_id: content_id.to_string(),
+                        content_id,
                     },
                 )
             }))
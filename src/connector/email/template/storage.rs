@@ -1,4 +1,7 @@
 use super::device_validation::DeviceValidationTemplate;
+use super::infection_report::InfectionReportTemplate;
+use super::infection_retraction::InfectionRetractionTemplate;
+use super::infection_summary::InfectionSummaryTemplate;
 use super::infection_warning::InfectionWarningTemplate;
 use std::env;
 
@@ -7,6 +10,9 @@ pub struct TemplateStorage {
     pub front_public_url: String,
     pub device_validation: DeviceValidationTemplate,
     pub infection_warning: InfectionWarningTemplate,
+    pub infection_retraction: InfectionRetractionTemplate,
+    pub infection_report: InfectionReportTemplate,
+    pub infection_summary: InfectionSummaryTemplate,
 }
 
 impl TemplateStorage {
@@ -17,6 +23,9 @@ impl TemplateStorage {
             front_public_url,
             device_validation: DeviceValidationTemplate::new(),
             infection_warning: InfectionWarningTemplate::new(),
+            infection_retraction: InfectionRetractionTemplate::new(),
+            infection_report: InfectionReportTemplate::new(),
+            infection_summary: InfectionSummaryTemplate::new(),
         }
     }
 }
@@ -0,0 +1,39 @@
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+const CHANNEL_CAPACITY: usize = 1024;
+
+pub struct Connector {
+    sender: broadcast::Sender<Uuid>,
+}
+
+impl Connector {
+    /// Notify subscribers that the gauge of a place changed
+    pub fn publish(&self, place_id: Uuid) {
+        // Only fails when nobody is listening
+        self.sender.send(place_id).ok();
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Uuid> {
+        self.sender.subscribe()
+    }
+}
+
+#[derive(Clone)]
+pub struct ConnectorBuilder {
+    sender: broadcast::Sender<Uuid>,
+}
+
+impl ConnectorBuilder {
+    pub fn new() -> ConnectorBuilder {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        ConnectorBuilder { sender }
+    }
+
+    pub fn create(&self) -> Connector {
+        Connector {
+            sender: self.sender.clone(),
+        }
+    }
+}
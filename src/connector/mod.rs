@@ -1,18 +1,24 @@
 pub mod configuration;
 pub mod email;
+pub mod gauge;
 pub mod local;
+pub mod push;
 
 #[derive(Clone)]
 pub struct ConnectorBuilder {
     configuration: configuration::ConnectorBuilder,
     local: local::ConnectorBuilder,
     email: email::ConnectorBuilder,
+    gauge: gauge::ConnectorBuilder,
+    push: push::ConnectorBuilder,
 }
 
 pub struct Connector {
     pub configuration: configuration::Connector,
     pub local: local::Connector,
     pub email: email::Connector,
+    pub gauge: gauge::Connector,
+    pub push: push::Connector,
 }
 
 impl ConnectorBuilder {
@@ -21,6 +27,8 @@ impl ConnectorBuilder {
             configuration: configuration::ConnectorBuilder::new(),
             local: local::ConnectorBuilder::new(),
             email: email::ConnectorBuilder::new(),
+            gauge: gauge::ConnectorBuilder::new(),
+            push: push::ConnectorBuilder::new(),
         }
     }
 
@@ -29,6 +37,8 @@ impl ConnectorBuilder {
             configuration: self.configuration.create(),
             local: self.local.create(),
             email: self.email.create(),
+            gauge: self.gauge.create(),
+            push: self.push.create(),
         }
     }
 }
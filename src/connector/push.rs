@@ -0,0 +1,500 @@
+use chrono::{DateTime, Utc};
+use custom_error::custom_error;
+use futures::future::BoxFuture;
+use hyper::client::connect::dns::{GaiResolver, Name};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Request, StatusCode, Uri};
+use hyper_tls::HttpsConnector;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey, EcPoint, PointConversionForm};
+use openssl::ecdsa::EcdsaSig;
+use openssl::nid::Nid;
+use openssl::pkey::Private;
+use openssl::sha::sha256;
+use serde::Serialize;
+use std::env;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower_service::Service;
+use uuid::Uuid;
+
+/// Seconds during which the push service keeps a message for an offline browser
+const MESSAGE_TTL: u32 = 24 * 60 * 60;
+/// Seconds during which a VAPID token is valid, 24 hours at most
+const VAPID_TOKEN_VALIDITY: i64 = 12 * 60 * 60;
+/// Maximum duration of a request to a push service
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+custom_error! { pub Error
+    Disabled = "Push notifications are disabled",
+    InvalidSubscription = "Invalid push subscription",
+    Serialization{source: serde_json::Error} = "Error while serializing push message: {source}",
+    Encryption{source: ece::Error} = "Error while encrypting push message: {source}",
+    Signature{source: openssl::error::ErrorStack} = "Error while signing push message: {source}",
+    Building{source: hyper::http::Error} = "Error while building push message: {source}",
+    Transport{source: hyper::Error} = "Error while sending push message: {source}",
+    Timeout = "Push service did not respond in time",
+    Expired = "Push subscription expired",
+    Rejected{status: StatusCode} = "Push message rejected with status {status}",
+}
+
+/// Message sent to the service worker of the front, which renders it
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PushMessage {
+    #[serde(rename_all = "camelCase")]
+    InfectionWarning { exposures_count: usize },
+    #[serde(rename_all = "camelCase")]
+    CheckinReminder {
+        checkin_id: Uuid,
+        place_name: String,
+        end_timestamp: DateTime<Utc>,
+    },
+}
+
+/// Subscription of a browser to a push service
+pub struct Subscription<'a> {
+    pub endpoint: &'a str,
+    /// Public key of the browser, base64url encoded
+    pub p256dh: &'a str,
+    /// Authentication secret of the browser, base64url encoded
+    pub auth: &'a str,
+}
+
+#[derive(Clone)]
+struct Vapid {
+    key: EcKey<Private>,
+    /// Uncompressed public key, base64url encoded
+    public_key: String,
+    /// Contact of the application server, mailto: or https: URL
+    subject: String,
+}
+
+type PushClient = Client<HttpsConnector<HttpConnector<PublicResolver>>>;
+
+pub struct Connector {
+    client: PushClient,
+    vapid: Option<Vapid>,
+    allow_local_endpoints: bool,
+}
+
+impl Connector {
+    pub fn is_enabled(&self) -> bool {
+        self.vapid.is_some()
+    }
+
+    /// Application server key used by browsers to subscribe, None if disabled
+    pub fn public_key(&self) -> Option<&str> {
+        self.vapid.as_ref().map(|vapid| vapid.public_key.as_str())
+    }
+
+    /// Check that an endpoint is a https URL outside of the local network
+    ///
+    /// Hosts resolving to a local address are refused when sending.
+    pub fn validate_endpoint(&self, endpoint: &str) -> Result<Uri, Error> {
+        let uri: Uri = endpoint.parse().map_err(|_| Error::InvalidSubscription)?;
+
+        if self.allow_local_endpoints {
+            return match uri.scheme_str() {
+                Some("https") | Some("http") => Ok(uri),
+                _ => Err(Error::InvalidSubscription),
+            };
+        }
+
+        if uri.scheme_str() != Some("https") {
+            return Err(Error::InvalidSubscription);
+        }
+
+        let host = uri
+            .host()
+            .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+            .ok_or(Error::InvalidSubscription)?;
+
+        let is_local = match host.parse::<IpAddr>() {
+            Ok(address) => !is_public(&address),
+            Err(_) => host == "localhost" || host.ends_with(".localhost"),
+        };
+
+        if is_local {
+            return Err(Error::InvalidSubscription);
+        }
+
+        Ok(uri)
+    }
+
+    // Encrypt and send a message using the Web Push protocol
+    pub async fn send(
+        &self,
+        subscription: &Subscription<'_>,
+        message: &PushMessage,
+    ) -> Result<(), Error> {
+        let vapid = self.vapid.as_ref().ok_or(Error::Disabled)?;
+
+        let uri = self.validate_endpoint(subscription.endpoint)?;
+
+        let audience = match (uri.scheme_str(), uri.authority()) {
+            (Some(scheme), Some(authority)) => format!("{}://{}", scheme, authority),
+            _ => return Err(Error::InvalidSubscription),
+        };
+
+        let p256dh = decode_base64url(subscription.p256dh)?;
+        let auth = decode_base64url(subscription.auth)?;
+
+        let payload = serde_json::to_vec(message)?;
+        let body = ece::encrypt(&p256dh, &auth, &payload)?;
+
+        let request = Request::post(uri)
+            .header("TTL", MESSAGE_TTL)
+            .header("Urgency", "high")
+            .header("Content-Encoding", "aes128gcm")
+            .header("Content-Type", "application/octet-stream")
+            .header(
+                "Authorization",
+                format!(
+                    "vapid t={}, k={}",
+                    vapid.token(&audience)?,
+                    vapid.public_key
+                ),
+            )
+            .body(Body::from(body))?;
+
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, self.client.request(request))
+            .await
+            .map_err(|_| Error::Timeout)??;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::NOT_FOUND | StatusCode::GONE => Err(Error::Expired),
+            status => Err(Error::Rejected { status }),
+        }
+    }
+}
+
+impl Vapid {
+    fn new(private_key: &str, subject: String) -> Vapid {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).expect("Unable to load P-256");
+
+        let private_number = decode_base64url(private_key)
+            .ok()
+            .and_then(|bytes| BigNum::from_slice(&bytes).ok())
+            .expect("Invalid PUSH_VAPID_PRIVATE_KEY");
+
+        let mut context = BigNumContext::new().expect("Unable to create BigNumContext");
+        let mut public_point = EcPoint::new(&group).expect("Unable to create EcPoint");
+        public_point
+            .mul_generator2(&group, &private_number, &mut context)
+            .expect("Invalid PUSH_VAPID_PRIVATE_KEY");
+
+        let public_key = public_point
+            .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut context)
+            .expect("Unable to encode VAPID public key");
+
+        let key = EcKey::from_private_components(&group, &private_number, &public_point)
+            .expect("Invalid PUSH_VAPID_PRIVATE_KEY");
+
+        key.check_key().expect("Invalid PUSH_VAPID_PRIVATE_KEY");
+
+        Vapid {
+            key,
+            public_key: base64::encode_config(public_key, base64::URL_SAFE_NO_PAD),
+            subject,
+        }
+    }
+
+    /// Sign a JWT identifying the application server to the push service
+    fn token(&self, audience: &str) -> Result<String, Error> {
+        let header = serde_json::json!({ "typ": "JWT", "alg": "ES256" });
+        let claims = serde_json::json!({
+            "aud": audience,
+            "exp": Utc::now().timestamp() + VAPID_TOKEN_VALIDITY,
+            "sub": self.subject,
+        });
+
+        let unsigned_token = format!(
+            "{}.{}",
+            base64::encode_config(header.to_string(), base64::URL_SAFE_NO_PAD),
+            base64::encode_config(claims.to_string(), base64::URL_SAFE_NO_PAD)
+        );
+
+        // ES256 signature is the concatenation of r and s
+        let signature = EcdsaSig::sign(&sha256(unsigned_token.as_bytes()), &self.key)?;
+        let mut raw_signature = signature.r().to_vec_padded(32)?;
+        raw_signature.extend(signature.s().to_vec_padded(32)?);
+
+        Ok(format!(
+            "{}.{}",
+            unsigned_token,
+            base64::encode_config(raw_signature, base64::URL_SAFE_NO_PAD)
+        ))
+    }
+}
+
+/// Resolver refusing hosts with a local address, unless local endpoints are allowed
+#[derive(Clone)]
+struct PublicResolver {
+    resolver: GaiResolver,
+    allow_local_endpoints: bool,
+}
+
+impl Service<Name> for PublicResolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, io::Error>>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.resolver.poll_ready(context)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let resolving = self.resolver.call(name);
+        let allow_local_endpoints = self.allow_local_endpoints;
+
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = resolving.await?.collect();
+
+            if !allow_local_endpoints && addresses.iter().any(|address| !is_public(&address.ip())) {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "Push endpoint resolves to a local address",
+                ));
+            }
+
+            Ok(addresses.into_iter())
+        })
+    }
+}
+
+/// Whether the address is reachable on the internet, not loopback, private or link-local
+fn is_public(address: &IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => {
+            let octets = address.octets();
+
+            !(address.is_loopback()
+                || address.is_private()
+                || address.is_link_local()
+                || address.is_unspecified()
+                || address.is_broadcast()
+                || address.is_multicast()
+                || address.is_documentation()
+                // Shared address space of carrier-grade NAT, 100.64.0.0/10
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64)
+                || octets[0] == 0)
+        }
+        IpAddr::V6(address) => {
+            if let Some(mapped) = address.to_ipv4_mapped() {
+                return is_public(&IpAddr::V4(mapped));
+            }
+
+            let first_segment = address.segments()[0];
+
+            !(address.is_loopback()
+                || address.is_unspecified()
+                || address.is_multicast()
+                // Unique local, fc00::/7
+                || (first_segment & 0xfe00) == 0xfc00
+                // Link-local, fe80::/10
+                || (first_segment & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+fn decode_base64url(value: &str) -> Result<Vec<u8>, Error> {
+    base64::decode_config(value.trim_end_matches('='), base64::URL_SAFE_NO_PAD)
+        .map_err(|_| Error::InvalidSubscription)
+}
+
+#[derive(Clone)]
+pub struct ConnectorBuilder {
+    client: PushClient,
+    vapid: Option<Vapid>,
+    allow_local_endpoints: bool,
+}
+
+impl ConnectorBuilder {
+    pub fn new() -> ConnectorBuilder {
+        // Push notifications are disabled without VAPID key
+        let vapid = env::var("PUSH_VAPID_PRIVATE_KEY")
+            .ok()
+            .filter(|private_key| !private_key.is_empty())
+            .map(|private_key| {
+                let subject =
+                    env::var("PUSH_VAPID_SUBJECT").expect("PUSH_VAPID_SUBJECT must be set");
+
+                Vapid::new(&private_key, subject)
+            });
+
+        // HTTP and local endpoints are only accepted for a local mock push service
+        let allow_local_endpoints: bool = env::var("PUSH_ALLOW_LOCAL_ENDPOINTS")
+            .map(|allow_str| {
+                allow_str
+                    .parse::<bool>()
+                    .expect("Invalid PUSH_ALLOW_LOCAL_ENDPOINTS")
+            })
+            .unwrap_or(false);
+
+        ConnectorBuilder::build(vapid, allow_local_endpoints)
+    }
+
+    fn build(vapid: Option<Vapid>, allow_local_endpoints: bool) -> ConnectorBuilder {
+        let mut http = HttpConnector::new_with_resolver(PublicResolver {
+            resolver: GaiResolver::new(),
+            allow_local_endpoints,
+        });
+        http.enforce_http(false);
+
+        let client = Client::builder().build::<_, Body>(HttpsConnector::new_with_connector(http));
+
+        ConnectorBuilder {
+            client,
+            vapid,
+            allow_local_endpoints,
+        }
+    }
+
+    pub fn create(&self) -> Connector {
+        Connector {
+            client: self.client.clone(),
+            vapid: self.vapid.clone(),
+            allow_local_endpoints: self.allow_local_endpoints,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::ec::EcKey;
+    use std::sync::{Arc, Mutex};
+    use warp::http::HeaderMap;
+    use warp::hyper::body::Bytes;
+    use warp::Filter;
+
+    fn generate_vapid() -> Vapid {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = EcKey::generate(&group).unwrap();
+        let private_key = key.private_key().to_vec_padded(32).unwrap();
+
+        Vapid::new(
+            &base64::encode_config(private_key, base64::URL_SAFE_NO_PAD),
+            String::from("mailto:test@example.com"),
+        )
+    }
+
+    #[tokio::test]
+    async fn send_to_local_mock_push_service() {
+        let received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>> = Arc::default();
+        let mock_received = received.clone();
+
+        let mock = warp::post()
+            .and(warp::path("push"))
+            .and(warp::header::headers_cloned())
+            .and(warp::body::bytes())
+            .map(move |headers: HeaderMap, body: Bytes| {
+                mock_received.lock().unwrap().push((headers, body));
+                warp::reply::with_status(warp::reply(), StatusCode::CREATED)
+            });
+
+        let (address, server) = warp::serve(mock).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let vapid = generate_vapid();
+        let connector = ConnectorBuilder::build(Some(vapid.clone()), true).create();
+
+        // Keys of the browser subscribing
+        let (browser_key, auth) = ece::generate_keypair_and_auth_secret().unwrap();
+        let endpoint = format!("http://{}/push", address);
+        let p256dh =
+            base64::encode_config(browser_key.pub_as_raw().unwrap(), base64::URL_SAFE_NO_PAD);
+        let auth_secret = base64::encode_config(auth, base64::URL_SAFE_NO_PAD);
+
+        connector
+            .send(
+                &Subscription {
+                    endpoint: &endpoint,
+                    p256dh: &p256dh,
+                    auth: &auth_secret,
+                },
+                &PushMessage::InfectionWarning { exposures_count: 2 },
+            )
+            .await
+            .unwrap();
+
+        let mut received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = received.remove(0);
+
+        assert_eq!(headers["Content-Encoding"], "aes128gcm");
+
+        // Payload is only readable with the keys of the browser
+        let payload = ece::decrypt(&browser_key.raw_components().unwrap(), &auth, &body).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&payload).unwrap(),
+            serde_json::json!({ "type": "infectionWarning", "exposuresCount": 2 })
+        );
+
+        // VAPID token is signed for the origin of the push service
+        let authorization = headers["Authorization"].to_str().unwrap();
+        let (token, public_key) = authorization
+            .strip_prefix("vapid t=")
+            .and_then(|value| value.split_once(", k="))
+            .unwrap();
+        assert_eq!(public_key, vapid.public_key);
+
+        let (unsigned_token, signature) = token.rsplit_once('.').unwrap();
+        let (_, claims) = unsigned_token.split_once('.').unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(
+            &base64::decode_config(claims, base64::URL_SAFE_NO_PAD).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(claims["aud"], format!("http://{}", address));
+
+        let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD).unwrap();
+        let signature = EcdsaSig::from_private_components(
+            BigNum::from_slice(&signature[..32]).unwrap(),
+            BigNum::from_slice(&signature[32..]).unwrap(),
+        )
+        .unwrap();
+        assert!(signature
+            .verify(&sha256(unsigned_token.as_bytes()), &vapid.key)
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn reject_local_endpoints() {
+        let connector = ConnectorBuilder::build(Some(generate_vapid()), false).create();
+
+        for endpoint in &[
+            "http://push.example.com/push",
+            "https://127.0.0.1/push",
+            "https://10.0.0.1/push",
+            "https://169.254.169.254/push",
+            "https://[::1]/push",
+            "https://[::ffff:192.168.0.1]/push",
+            "https://localhost/push",
+        ] {
+            assert!(
+                connector.validate_endpoint(endpoint).is_err(),
+                "{} accepted",
+                endpoint
+            );
+        }
+
+        assert!(connector
+            .validate_endpoint("https://push.example.com/push")
+            .is_ok());
+
+        // Hosts resolving to a local address are refused when connecting
+        let mut resolver = PublicResolver {
+            resolver: GaiResolver::new(),
+            allow_local_endpoints: false,
+        };
+        let resolved = resolver.call("localhost".parse().unwrap()).await;
+        assert_eq!(
+            resolved.unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+}
@@ -1,6 +1,6 @@
 #[macro_use]
 extern crate clap;
-#[cfg(any(target_os = "unix", target_os = "linux"))]
+#[cfg(unix)]
 extern crate openssl; // Should be before diesel
 #[macro_use]
 extern crate diesel;
@@ -15,11 +15,11 @@ mod connector;
 mod model;
 mod security;
 mod serve;
+mod task;
 mod types;
 
 use connector::ConnectorBuilder;
 use dotenv::dotenv;
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() {
@@ -1,3 +1,5 @@
+use super::super::organization::Organization;
+use super::super::place::Place;
 use super::super::schema::checkin;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
@@ -6,18 +8,37 @@ use uuid::Uuid;
 pub struct Checkin {
     pub id: Uuid,
     pub place_id: Uuid,
-    pub session_id: Uuid,
-    pub user_id: Uuid,
     pub start_timestamp: DateTime<Utc>,
     pub end_timestamp: DateTime<Utc>,
     pub duration: i64,
     pub potential_infection: bool,
-    pub confirmed: bool,
-    pub updated_at: DateTime<Utc>,
-    pub created_at: DateTime<Utc>,
     pub number: i64,
 }
 
+pub type CheckinColumns = (
+    checkin::id,
+    checkin::place_id,
+    checkin::start_timestamp,
+    checkin::end_timestamp,
+    checkin::duration,
+    checkin::potential_infection,
+    checkin::number,
+);
+
+/// Columns read into `Checkin`, in the order of its fields
+pub const CHECKIN_COLUMNS: CheckinColumns = (
+    checkin::id,
+    checkin::place_id,
+    checkin::start_timestamp,
+    checkin::end_timestamp,
+    checkin::duration,
+    checkin::potential_infection,
+    checkin::number,
+);
+
+/// Checkin with its place and the organization of the place
+pub type CheckinWithPlace = (Checkin, (Place, Organization));
+
 #[derive(Insertable)]
 #[table_name = "checkin"]
 pub struct CheckinInsert {
@@ -30,3 +51,28 @@ pub struct CheckinInsert {
     pub confirmed: bool,
     pub number: i64,
 }
+
+#[derive(QueryableByName)]
+pub struct CheckinIdRow {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub id: Uuid,
+}
+
+#[derive(QueryableByName)]
+pub struct CheckinPlaceRow {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub place_id: Uuid,
+}
+
+/// Checkin ending soon whose session is subscribed to push notifications
+#[derive(QueryableByName)]
+pub struct CheckinReminder {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub id: Uuid,
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub session_id: Uuid,
+    #[sql_type = "diesel::sql_types::Text"]
+    pub place_name: String,
+    #[sql_type = "diesel::sql_types::Timestamptz"]
+    pub end_timestamp: DateTime<Utc>,
+}
@@ -1,14 +1,16 @@
 mod common;
 
 use super::error::Error;
-use super::organization::Organization;
-use super::place::Place;
+use super::organization::ORGANIZATION_COLUMNS;
+use super::place::PLACE_COLUMNS;
 use super::schema::checkin::dsl;
-use super::schema::{organization, place, user};
-use super::user::User;
+use super::schema::{organization, place};
 use crate::connector::Connector;
 use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Nullable, Timestamptz};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 pub use common::*;
@@ -16,48 +18,112 @@ pub use common::*;
 pub fn get_all_with_user(
     connector: &Connector,
     user_id: &Uuid,
-) -> Result<Vec<(Checkin, (Place, Organization))>, Error> {
+) -> Result<Vec<CheckinWithPlace>, Error> {
     let connection = connector.local.pool.get()?;
 
     dsl::checkin
         .inner_join(place::dsl::place.inner_join(organization::dsl::organization))
+        .select((CHECKIN_COLUMNS, (PLACE_COLUMNS, ORGANIZATION_COLUMNS)))
         .filter(dsl::user_id.eq(user_id).and(dsl::confirmed.eq(true)))
         .order(dsl::start_timestamp.desc())
-        .load::<(Checkin, (Place, Organization))>(&connection)
+        .load::<CheckinWithPlace>(&connection)
         .map_err(|error| error.into())
 }
 
-pub fn get(
-    connector: &Connector,
-    checkin_id: &Uuid,
-) -> Result<(Checkin, (Place, Organization)), Error> {
+pub fn get(connector: &Connector, checkin_id: &Uuid) -> Result<CheckinWithPlace, Error> {
     let connection = connector.local.pool.get()?;
 
     dsl::checkin
         .inner_join(place::dsl::place.inner_join(organization::dsl::organization))
+        .select((CHECKIN_COLUMNS, (PLACE_COLUMNS, ORGANIZATION_COLUMNS)))
         .filter(dsl::id.eq(checkin_id))
-        .first::<(Checkin, (Place, Organization))>(&connection)
+        .first::<CheckinWithPlace>(&connection)
         .map_err(|error| error.into())
 }
 
 pub fn delete_all_with_user(connector: &Connector, user_id: &Uuid) -> Result<(), Error> {
     let connection = connector.local.pool.get()?;
+    let now = Utc::now();
 
-    diesel::delete(dsl::checkin.filter(dsl::user_id.eq(user_id)))
-        .execute(&connection)
-        .map_err(|error| error.into())
-        .map(|_| ())
+    let changed_places_ids = connection.transaction::<Vec<Uuid>, Error, _>(|| {
+        // Active checkins are still counted in gauges
+        let active_checkins = dsl::checkin
+            .select((dsl::place_id, dsl::number))
+            .filter(
+                dsl::user_id
+                    .eq(user_id)
+                    .and(dsl::start_timestamp.le(now))
+                    .and(dsl::end_timestamp.ge(now)),
+            )
+            .for_update()
+            .load::<(Uuid, i64)>(&connection)?;
+
+        diesel::delete(dsl::checkin.filter(dsl::user_id.eq(user_id))).execute(&connection)?;
+
+        // Sorted by place so that rows are locked in a deterministic order
+        let mut numbers_by_place: BTreeMap<Uuid, i64> = BTreeMap::new();
+        for (place_id, number) in active_checkins {
+            *numbers_by_place.entry(place_id).or_default() += number;
+        }
+
+        let mut changed_places_ids = Vec::new();
+        for (place_id, number) in numbers_by_place {
+            if super::place::add_to_gauge(
+                &connection,
+                &connector.configuration.gauge,
+                &place_id,
+                -number,
+            )? {
+                changed_places_ids.push(place_id);
+            }
+        }
+
+        Ok(changed_places_ids)
+    })?;
+
+    for place_id in changed_places_ids {
+        connector.gauge.publish(place_id);
+    }
+
+    Ok(())
 }
 
 pub fn insert(connector: &Connector, checkin: &CheckinInsert) -> Result<Uuid, Error> {
     let connection = connector.local.pool.get()?;
 
-    // Insert user if not exists, otherwise update its email which the unhashed version of the login
-    diesel::insert_into(dsl::checkin)
-        .values(checkin)
-        .returning(dsl::id)
-        .get_result(&connection)
-        .map_err(|error| error.into())
+    let (id, gauge_changed) = connection.transaction::<(Uuid, bool), Error, _>(|| {
+        // Lock place so that concurrent checkins cannot exceed its maximum gauge
+        let place = super::place::get_for_update(&connection, &checkin.place_id)?;
+
+        // The stored gauge still counts checkins ended since the last refresh
+        let active_visitors = super::place::count_active_visitors(&connection, &place.id)?;
+
+        if let Some(maximum_gauge) = place.maximum_gauge {
+            if maximum_gauge < active_visitors + checkin.number {
+                return Err(Error::MaximumGaugeReached);
+            }
+        }
+
+        let id = diesel::insert_into(dsl::checkin)
+            .values(checkin)
+            .returning(dsl::id)
+            .get_result(&connection)?;
+
+        let gauge_changed = super::place::add_to_gauge(
+            &connection,
+            &connector.configuration.gauge,
+            &checkin.place_id,
+            active_visitors - place.current_gauge + checkin.number,
+        )?;
+
+        Ok((id, gauge_changed))
+    })?;
+
+    if gauge_changed {
+        connector.gauge.publish(checkin.place_id);
+    }
+
+    Ok(id)
 }
 
 pub fn confirm(connector: &Connector, session_id: &Uuid) -> Result<(), Error> {
@@ -72,60 +138,211 @@ pub fn confirm(connector: &Connector, session_id: &Uuid) -> Result<(), Error> {
 
 pub fn leave(connector: &Connector, user_id: &Uuid, checkin_id: &Uuid) -> Result<(), Error> {
     let connection = connector.local.pool.get()?;
+    let now = Utc::now();
 
-    let checkin = dsl::checkin
-        .filter(dsl::id.eq(checkin_id))
-        .first::<Checkin>(&connection)?;
+    let changed_place_id = connection.transaction::<Option<Uuid>, Error, _>(|| {
+        let checkin = dsl::checkin
+            .filter(dsl::id.eq(checkin_id).and(dsl::user_id.eq(user_id)))
+            .select(CHECKIN_COLUMNS)
+            .for_update()
+            .first::<Checkin>(&connection)?;
 
-    let now = Utc::now();
-    let duration = (now - checkin.start_timestamp).num_minutes();
+        let duration = (now - checkin.start_timestamp).num_minutes();
 
-    diesel::update(dsl::checkin.filter(dsl::user_id.eq(user_id).and(dsl::id.eq(checkin_id))))
-        .set((dsl::end_timestamp.eq(now), dsl::duration.eq(duration)))
-        .execute(&connection)
-        .map(|_| ())
-        .map_err(|error| error.into())
+        diesel::update(dsl::checkin.find(checkin_id))
+            .set((dsl::end_timestamp.eq(now), dsl::duration.eq(duration)))
+            .execute(&connection)?;
+
+        // Only remove visitors still counted in the gauge
+        if checkin.start_timestamp <= now
+            && checkin.end_timestamp >= now
+            && super::place::add_to_gauge(
+                &connection,
+                &connector.configuration.gauge,
+                &checkin.place_id,
+                -checkin.number,
+            )?
+        {
+            Ok(Some(checkin.place_id))
+        } else {
+            Ok(None)
+        }
+    })?;
+
+    if let Some(place_id) = changed_place_id {
+        connector.gauge.publish(place_id);
+    }
+
+    Ok(())
+}
+
+/// Close check-ins still active after the evacuation of their place and refresh gauges
+pub fn leave_all_evacuated(connector: &Connector) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+
+    let rows = diesel::sql_query(
+        "UPDATE checkin
+        SET
+            end_timestamp = evacuation.evacuation_timestamp,
+            duration = FLOOR(EXTRACT(EPOCH FROM (evacuation.evacuation_timestamp - checkin.start_timestamp)) / 60)
+        FROM (SELECT DISTINCT ON (checkin.id) checkin.id AS checkin_id, computed.evacuation_timestamp
+            FROM checkin
+            INNER JOIN opening_hour_computed AS computed
+            ON computed.place_id = checkin.place_id
+            WHERE computed.evacuation_timestamp <= NOW()
+                AND checkin.start_timestamp < computed.evacuation_timestamp
+                AND checkin.end_timestamp > computed.evacuation_timestamp
+            ORDER BY checkin.id, computed.evacuation_timestamp ASC) AS evacuation
+        WHERE checkin.id = evacuation.checkin_id
+        RETURNING checkin.place_id",
+    )
+    .load::<CheckinPlaceRow>(&connection)?;
+
+    let mut places_ids: Vec<Uuid> = rows.iter().map(|row| row.place_id).collect();
+    places_ids.sort();
+    places_ids.dedup();
+
+    if !places_ids.is_empty() {
+        super::place::refresh_gauges_with_places(connector, &places_ids)?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Delete unconfirmed checkins of sessions never confirmed in time and refresh gauges
+pub fn delete_all_unconfirmed_expired(connector: &Connector) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+
+    let rows = diesel::sql_query(
+        "DELETE FROM checkin
+        USING session
+        WHERE session.id = checkin.session_id
+            AND session.confirmed = FALSE
+            AND checkin.confirmed = FALSE
+            AND session.created_at <= NOW() - INTERVAL '1 minute' * $1
+        RETURNING checkin.place_id",
+    )
+    .bind::<BigInt, _>(connector.configuration.session.confirmation_ttl)
+    .load::<CheckinPlaceRow>(&connection)?;
+
+    let mut places_ids: Vec<Uuid> = rows.iter().map(|row| row.place_id).collect();
+    places_ids.sort();
+    places_ids.dedup();
+
+    if !places_ids.is_empty() {
+        super::place::refresh_gauges_with_places(connector, &places_ids)?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Mark as reminded the checkins ending in the next minutes, returning them
+///
+/// Only checkins of sessions subscribed to push notifications are reminded,
+/// at most once.
+pub fn set_all_reminded(connector: &Connector) -> Result<Vec<CheckinReminder>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        "UPDATE checkin
+        SET reminded_at = NOW()
+        FROM place
+        WHERE place.id = checkin.place_id
+            AND checkin.confirmed = TRUE
+            AND checkin.reminded_at IS NULL
+            AND checkin.end_timestamp > NOW()
+            AND checkin.end_timestamp <= NOW() + INTERVAL '1 minute' * $1
+            AND EXISTS (
+                SELECT 1
+                FROM push_subscription
+                WHERE push_subscription.session_id = checkin.session_id
+            )
+        RETURNING checkin.id, checkin.session_id, place.name AS place_name, checkin.end_timestamp",
+    )
+    .bind::<BigInt, _>(connector.configuration.checkin.reminder_minutes)
+    .load::<CheckinReminder>(&connection)
+    .map_err(|error| error.into())
 }
 
+/// Flag checkins present at least the minimum overlap during the period
+///
+/// The minimum overlap in minutes of the place overrides the global one and
+/// checkins of the excluded user, reporting the infection, are not flagged.
 pub fn enable_potential_infections(
-    connector: &Connector,
+    connection: &PgConnection,
     places_ids: &Vec<Uuid>,
     start_timestamp: &DateTime<Utc>,
     end_timestamp: &DateTime<Utc>,
+    minimum_overlap: i64,
+    excluded_user_id: Option<&Uuid>,
 ) -> Result<(), Error> {
-    let connection = connector.local.pool.get()?;
-
-    diesel::update(
-        dsl::checkin.filter(
-            dsl::place_id
-                .eq_any(places_ids)
-                .and(dsl::start_timestamp.le(end_timestamp))
-                .and(dsl::end_timestamp.ge(start_timestamp)),
-        ),
+    diesel::sql_query(
+        r#"
+        UPDATE checkin
+        SET potential_infection = TRUE
+        FROM place
+        WHERE place.id = checkin.place_id
+            AND checkin.place_id = ANY($1)
+            AND checkin.start_timestamp <= $3
+            AND checkin.end_timestamp >= $2
+            AND LEAST(checkin.end_timestamp, $3) - GREATEST(checkin.start_timestamp, $2)
+                >= INTERVAL '1 minute' * COALESCE(place.minimum_overlap, $4)
+            AND checkin.user_id IS DISTINCT FROM $5
+        "#,
     )
-    .set(dsl::potential_infection.eq(true))
-    .execute(&connection)
+    .bind::<Array<diesel::sql_types::Uuid>, _>(places_ids)
+    .bind::<Timestamptz, _>(start_timestamp)
+    .bind::<Timestamptz, _>(end_timestamp)
+    .bind::<BigInt, _>(minimum_overlap)
+    .bind::<Nullable<diesel::sql_types::Uuid>, _>(excluded_user_id)
+    .execute(connection)
     .map(|_| ())
     .map_err(|error| error.into())
 }
 
-pub fn get_potential_infections(
-    connector: &Connector,
+/// Clear potential infections of the period not covered anymore by any approved infection
+///
+/// The kept infection, waiting for a new review, covers checkins as if still approved.
+/// Returns the ids of cleared checkins.
+pub fn disable_potential_infections(
+    connection: &PgConnection,
     places_ids: &Vec<Uuid>,
     start_timestamp: &DateTime<Utc>,
     end_timestamp: &DateTime<Utc>,
-) -> Result<Vec<(Checkin, User, Place)>, Error> {
-    let connection = connector.local.pool.get()?;
-
-    dsl::checkin
-        .inner_join(user::dsl::user)
-        .inner_join(place::dsl::place)
-        .filter(
-            dsl::place_id
-                .eq_any(places_ids)
-                .and(dsl::start_timestamp.le(end_timestamp))
-                .and(dsl::end_timestamp.ge(start_timestamp)),
-        )
-        .load::<(Checkin, User, Place)>(&connection)
-        .map_err(|error| error.into())
+    minimum_overlap: i64,
+    kept_infection_id: Option<&Uuid>,
+) -> Result<Vec<Uuid>, Error> {
+    diesel::sql_query(
+        r#"
+        UPDATE checkin
+        SET potential_infection = FALSE
+        FROM place
+        WHERE place.id = checkin.place_id
+            AND checkin.potential_infection = TRUE
+            AND checkin.place_id = ANY($1)
+            AND checkin.start_timestamp <= $3
+            AND checkin.end_timestamp >= $2
+            AND NOT EXISTS (
+                SELECT 1
+                FROM infection
+                WHERE (infection.status = 'approved' OR infection.id = $5)
+                    AND checkin.user_id IS DISTINCT FROM infection.reported_by
+                    AND checkin.place_id = ANY(infection.places_ids)
+                    AND checkin.start_timestamp <= infection.end_timestamp
+                    AND checkin.end_timestamp >= infection.start_timestamp
+                    AND LEAST(checkin.end_timestamp, infection.end_timestamp)
+                        - GREATEST(checkin.start_timestamp, infection.start_timestamp)
+                        >= INTERVAL '1 minute' * COALESCE(place.minimum_overlap, $4)
+            )
+        RETURNING checkin.id
+        "#,
+    )
+    .bind::<Array<diesel::sql_types::Uuid>, _>(places_ids)
+    .bind::<Timestamptz, _>(start_timestamp)
+    .bind::<Timestamptz, _>(end_timestamp)
+    .bind::<BigInt, _>(minimum_overlap)
+    .bind::<Nullable<diesel::sql_types::Uuid>, _>(kept_infection_id)
+    .load::<CheckinIdRow>(connection)
+    .map(|rows| rows.into_iter().map(|row| row.id).collect())
+    .map_err(|error| error.into())
 }
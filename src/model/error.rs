@@ -4,9 +4,11 @@ use tracing::error;
 
 custom_error! { pub Error
     LocalConnectionFailed{source: r2d2::Error} = "Unable to connect to local database ({source}).",
-    DatabaseError{diesel_error: diesel::result::Error} = "Unable to run some operations on updatable model ({diesel_error}).",
+    Database{diesel_error: diesel::result::Error} = "Unable to run some operations on updatable model ({diesel_error}).",
     NotFound = "Not found.",
     NotFoundWithName{name: String} = "{name} not found.",
+    MaximumGaugeReached = "Maximum gauge reached.",
+    ExportFailed{source: csv::Error} = "Unable to write export ({source}).",
 }
 
 impl Error {
@@ -30,7 +32,7 @@ impl From<diesel::result::Error> for Error {
     fn from(error: diesel::result::Error) -> Self {
         match error {
             diesel::result::Error::NotFound => Error::NotFound,
-            _ => Error::DatabaseError {
+            _ => Error::Database {
                 diesel_error: error,
             },
         }
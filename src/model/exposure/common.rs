@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use diesel::sql_types::{BigInt, Nullable, Text, Timestamptz};
+use uuid::Uuid;
+
+#[derive(QueryableByName)]
+pub struct Exposure {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub checkin_id: Uuid,
+    #[sql_type = "Timestamptz"]
+    pub start_timestamp: DateTime<Utc>,
+    #[sql_type = "Timestamptz"]
+    pub end_timestamp: DateTime<Utc>,
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub place_id: Uuid,
+    #[sql_type = "Text"]
+    pub place_name: String,
+    #[sql_type = "Text"]
+    pub organization_name: String,
+    /// Number of approved infections covering the checkin
+    #[sql_type = "BigInt"]
+    pub infections_count: i64,
+    /// Longest overlap in minutes with an approved infection
+    #[sql_type = "Nullable<BigInt>"]
+    pub overlap_minutes: Option<i64>,
+    /// Highest gauge of the place during the checkin, the current one once snapshots expired
+    #[sql_type = "Nullable<BigInt>"]
+    pub gauge: Option<i64>,
+}
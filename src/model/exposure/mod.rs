@@ -0,0 +1,77 @@
+mod common;
+
+use super::error::Error;
+use crate::connector::Connector;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use uuid::Uuid;
+
+pub use common::*;
+
+/// Get confirmed checkins of the user flagged as potentially infected
+///
+/// Only infections overlapping long enough to warn the visitor are counted. Gauge
+/// snapshots being only taken on change, the last one before the checkin still applies.
+pub fn get_all_with_user(connector: &Connector, user_id: &Uuid) -> Result<Vec<Exposure>, Error> {
+    let connection = connector.local.pool.get()?;
+    let minimum_overlap = connector.configuration.infection.minimum_overlap;
+
+    diesel::sql_query(
+        r#"
+        SELECT
+            checkin.id AS checkin_id,
+            checkin.start_timestamp,
+            checkin.end_timestamp,
+            place.id AS place_id,
+            place.name AS place_name,
+            organization.name AS organization_name,
+            exposure.infections_count,
+            exposure.overlap_minutes,
+            COALESCE(GREATEST(during.gauge, before.gauge), place.current_gauge) AS gauge
+        FROM checkin
+        INNER JOIN place ON place.id = checkin.place_id
+        INNER JOIN organization ON organization.id = place.organization_id
+        CROSS JOIN LATERAL (
+            SELECT
+                COUNT(*) AS infections_count,
+                MAX(CAST(FLOOR(EXTRACT(EPOCH FROM
+                    LEAST(checkin.end_timestamp, infection.end_timestamp)
+                    - GREATEST(checkin.start_timestamp, infection.start_timestamp)
+                ) / 60) AS int8)) AS overlap_minutes
+            FROM infection
+            WHERE infection.status = 'approved'
+                AND checkin.user_id IS DISTINCT FROM infection.reported_by
+                AND checkin.place_id = ANY(infection.places_ids)
+                AND checkin.start_timestamp <= infection.end_timestamp
+                AND checkin.end_timestamp >= infection.start_timestamp
+                AND LEAST(checkin.end_timestamp, infection.end_timestamp)
+                    - GREATEST(checkin.start_timestamp, infection.start_timestamp)
+                    >= INTERVAL '1 minute' * COALESCE(place.minimum_overlap, $2)
+        ) AS exposure
+        CROSS JOIN LATERAL (
+            SELECT MAX(gauge_snapshot.gauge) AS gauge
+            FROM gauge_snapshot
+            WHERE gauge_snapshot.place_id = checkin.place_id
+                AND gauge_snapshot.created_at >= checkin.start_timestamp
+                AND gauge_snapshot.created_at < checkin.end_timestamp
+        ) AS during
+        LEFT JOIN LATERAL (
+            SELECT gauge_snapshot.gauge
+            FROM gauge_snapshot
+            WHERE gauge_snapshot.place_id = checkin.place_id
+                AND gauge_snapshot.created_at < checkin.start_timestamp
+            ORDER BY gauge_snapshot.created_at DESC
+            LIMIT 1
+        ) AS before ON TRUE
+        WHERE checkin.user_id = $1
+            AND checkin.confirmed = TRUE
+            AND checkin.potential_infection = TRUE
+            AND exposure.infections_count > 0
+        ORDER BY checkin.start_timestamp DESC
+        "#,
+    )
+    .bind::<diesel::sql_types::Uuid, _>(user_id)
+    .bind::<BigInt, _>(minimum_overlap)
+    .load::<Exposure>(&connection)
+    .map_err(|error| error.into())
+}
@@ -1,18 +1,41 @@
 use super::super::schema::infection;
+use super::super::types::InfectionStatus;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 #[derive(Queryable)]
 pub struct Infection {
     pub id: Uuid,
-    pub organization_id: Uuid,
     pub places_ids: Vec<Uuid>,
     pub start_timestamp: DateTime<Utc>,
     pub end_timestamp: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-    pub created_at: DateTime<Utc>,
+    pub status: InfectionStatus,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    /// Public user who reported a positive test, None if declared by the organization
+    pub reported_by: Option<Uuid>,
 }
 
+pub type InfectionColumns = (
+    infection::id,
+    infection::places_ids,
+    infection::start_timestamp,
+    infection::end_timestamp,
+    infection::status,
+    infection::reviewed_at,
+    infection::reported_by,
+);
+
+/// Columns read into `Infection`, in the order of its fields
+pub const INFECTION_COLUMNS: InfectionColumns = (
+    infection::id,
+    infection::places_ids,
+    infection::start_timestamp,
+    infection::end_timestamp,
+    infection::status,
+    infection::reviewed_at,
+    infection::reported_by,
+);
+
 #[derive(Insertable)]
 #[table_name = "infection"]
 pub struct InfectionInsert {
@@ -20,4 +43,13 @@ pub struct InfectionInsert {
     pub places_ids: Vec<Uuid>,
     pub start_timestamp: DateTime<Utc>,
     pub end_timestamp: DateTime<Utc>,
+    pub reported_by: Option<Uuid>,
+}
+
+#[derive(AsChangeset)]
+#[table_name = "infection"]
+pub struct InfectionUpdate {
+    pub places_ids: Vec<Uuid>,
+    pub start_timestamp: DateTime<Utc>,
+    pub end_timestamp: DateTime<Utc>,
 }
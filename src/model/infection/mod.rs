@@ -1,13 +1,20 @@
 mod common;
 
-use super::checkin;
-use super::error::Error;
-use super::organization::Organization;
+use super::checkin::{self, Checkin};
+use super::error::{is_one, Error};
+use super::notification;
+use super::organization::{Organization, ORGANIZATION_COLUMNS};
+use super::place::Place;
 use super::schema::{infection::dsl, organization};
 use crate::connector::Connector;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono_tz::Tz;
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
+use std::cmp::{max, min};
 use uuid::Uuid;
 
+pub use super::types::InfectionStatus;
 pub use common::*;
 
 pub fn get_all_with_organization(
@@ -18,6 +25,7 @@ pub fn get_all_with_organization(
 
     dsl::infection
         .inner_join(organization::dsl::organization)
+        .select((INFECTION_COLUMNS, ORGANIZATION_COLUMNS))
         .filter(organization::dsl::id.eq(organization_id))
         .order(dsl::start_timestamp.desc())
         .load::<(Infection, Organization)>(&connection)
@@ -32,25 +40,328 @@ pub fn get_with_organization(
 
     dsl::infection
         .inner_join(organization::dsl::organization)
+        .select((INFECTION_COLUMNS, ORGANIZATION_COLUMNS))
         .filter(dsl::id.eq(infection_id))
         .first::<(Infection, Organization)>(&connection)
         .map_err(|error| error.into())
 }
 
+/// Get infections with the status, optionally restricted to the ones involving some places
+pub fn get_all_with_status(
+    connector: &Connector,
+    status: InfectionStatus,
+    places_ids: Option<&Vec<Uuid>>,
+) -> Result<Vec<(Infection, Organization)>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    let mut query = dsl::infection
+        .inner_join(organization::dsl::organization)
+        .select((INFECTION_COLUMNS, ORGANIZATION_COLUMNS))
+        .filter(dsl::status.eq(status))
+        .into_boxed();
+
+    if let Some(places_ids) = places_ids {
+        query = query.filter(dsl::places_ids.overlaps_with(places_ids));
+    }
+
+    query
+        .order(dsl::start_timestamp.desc())
+        .load::<(Infection, Organization)>(&connection)
+        .map_err(|error| error.into())
+}
+
+/// Insert the infection pending for review by a health authority
 pub fn insert(connector: &Connector, infection: &InfectionInsert) -> Result<Uuid, Error> {
     let connection = connector.local.pool.get()?;
 
-    let id = diesel::insert_into(dsl::infection)
+    diesel::insert_into(dsl::infection)
         .values(infection)
         .returning(dsl::id)
-        .get_result(&connection)?;
+        .get_result(&connection)
+        .map_err(|error| error.into())
+}
+
+/// Insert an infection for each checkin of the user during the contagious period
+///
+/// Infections are pending for review and span the whole days of the checkin within the
+/// contagious period, so that organizations informed after approval cannot recognize the
+/// user among their visitors. Returns the number of inserted infections.
+pub fn insert_reported(
+    connector: &Connector,
+    user_id: &Uuid,
+    start_timestamp: &DateTime<Utc>,
+    end_timestamp: &DateTime<Utc>,
+) -> Result<usize, Error> {
+    let checkins: Vec<(Checkin, (Place, Organization))> =
+        checkin::get_all_with_user(connector, user_id)?
+            .into_iter()
+            .filter(|(checkin, _)| {
+                checkin.start_timestamp <= *end_timestamp
+                    && checkin.end_timestamp >= *start_timestamp
+            })
+            .collect();
+
+    let connection = connector.local.pool.get()?;
+
+    connection.transaction::<usize, Error, _>(|| {
+        // Do not report twice the same place and day
+        let mut reported: Vec<(Vec<Uuid>, DateTime<Utc>)> = dsl::infection
+            .select((dsl::places_ids, dsl::start_timestamp))
+            .filter(dsl::reported_by.eq(user_id))
+            .load(&connection)?;
+
+        let mut count = 0;
+
+        for (checkin, (place, organization)) in checkins {
+            let (reported_start, reported_end) =
+                widen_to_days(&checkin, &place.timezone.tz, start_timestamp, end_timestamp);
+
+            let already_reported = reported.iter().any(|(places_ids, start_timestamp)| {
+                places_ids.contains(&place.id) && *start_timestamp == reported_start
+            });
+
+            if already_reported {
+                continue;
+            }
+
+            diesel::insert_into(dsl::infection)
+                .values(&InfectionInsert {
+                    organization_id: organization.id,
+                    places_ids: vec![place.id],
+                    start_timestamp: reported_start,
+                    end_timestamp: reported_end,
+                    reported_by: Some(*user_id),
+                })
+                .execute(&connection)?;
+
+            reported.push((vec![place.id], reported_start));
+            count += 1;
+        }
+
+        Ok(count)
+    })
+}
+
+/// Widen the checkin to the local days it spans, within the contagious period
+fn widen_to_days(
+    checkin: &Checkin,
+    timezone: &Tz,
+    start_timestamp: &DateTime<Utc>,
+    end_timestamp: &DateTime<Utc>,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let day_start = |timestamp: &DateTime<Utc>, offset: i64| {
+        let date = timestamp.with_timezone(timezone).date().naive_local() + Duration::days(offset);
+
+        // Midnight may not exist on a timezone change, keep the checkin bound then
+        timezone
+            .from_local_datetime(&date.and_hms(0, 0, 0))
+            .earliest()
+            .map(|day_start| day_start.with_timezone(&Utc))
+            .unwrap_or(*timestamp)
+    };
+
+    (
+        max(day_start(&checkin.start_timestamp, 0), *start_timestamp),
+        min(day_start(&checkin.end_timestamp, 1), *end_timestamp),
+    )
+}
+
+/// Update the infection and submit it again for review
+///
+/// Checkins still covered by an approved infection keep their flag until the new review,
+/// whose approval only warns visitors not warned yet. Visitors removed by the new period
+/// or places are not warned if not done yet and optionally notified of the retraction.
+pub fn update(
+    connector: &Connector,
+    infection_id: &Uuid,
+    infection: &InfectionUpdate,
+    notify_retractions: bool,
+) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+    let minimum_overlap = connector.configuration.infection.minimum_overlap;
+
+    connection.transaction::<(), Error, _>(|| {
+        let previous = dsl::infection
+            .select(INFECTION_COLUMNS)
+            .filter(dsl::id.eq(infection_id))
+            .for_update()
+            .first::<Infection>(&connection)?;
+
+        let count = diesel::update(dsl::infection.filter(dsl::id.eq(infection_id)))
+            .set((
+                infection,
+                dsl::status.eq(InfectionStatus::Pending),
+                dsl::reviewed_by.eq(None::<Uuid>),
+                dsl::reviewed_at.eq(None::<DateTime<Utc>>),
+            ))
+            .execute(&connection)?;
+
+        is_one(count, "Infection")?;
+
+        let kept_infection_id = if previous.status == InfectionStatus::Approved {
+            Some(infection_id)
+        } else {
+            None
+        };
+
+        let cleared_checkins_ids = checkin::disable_potential_infections(
+            &connection,
+            &previous.places_ids,
+            &previous.start_timestamp,
+            &previous.end_timestamp,
+            minimum_overlap,
+            kept_infection_id,
+        )?;
+
+        notification::delete_pending(&connection, infection_id, None)?;
+
+        if notify_retractions {
+            notification::insert_retractions(&connection, infection_id, &cleared_checkins_ids)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Approve the pending infection, flagging potential infections and queuing warnings
+pub fn approve(
+    connector: &Connector,
+    infection_id: &Uuid,
+    reviewer_id: &Uuid,
+) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+    let minimum_overlap = connector.configuration.infection.minimum_overlap;
+
+    connection.transaction::<(), Error, _>(|| {
+        let infection = set_reviewed(
+            &connection,
+            infection_id,
+            reviewer_id,
+            vec![InfectionStatus::Pending],
+            InfectionStatus::Approved,
+        )?;
+
+        checkin::enable_potential_infections(
+            &connection,
+            &infection.places_ids,
+            &infection.start_timestamp,
+            &infection.end_timestamp,
+            minimum_overlap,
+            infection.reported_by.as_ref(),
+        )?;
+
+        notification::insert_warnings(&connection, infection_id, minimum_overlap)?;
+        notification::insert_summary(&connection, infection_id)?;
+
+        // Organizations learn of self-reported infections only once approved
+        if infection.reported_by.is_some() {
+            notification::insert_report(&connection, infection_id)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Reject the pending infection, or retract the approved one, no visitor is warned anymore
+///
+/// Flags kept from a previous approval are cleared and visitors already warned are
+/// notified of the retraction.
+pub fn reject(connector: &Connector, infection_id: &Uuid, reviewer_id: &Uuid) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+    let minimum_overlap = connector.configuration.infection.minimum_overlap;
+
+    connection.transaction::<(), Error, _>(|| {
+        let infection = set_reviewed(
+            &connection,
+            infection_id,
+            reviewer_id,
+            vec![InfectionStatus::Pending, InfectionStatus::Approved],
+            InfectionStatus::Rejected,
+        )?;
+
+        notification::delete_pending(&connection, infection_id, None)?;
+
+        let cleared_checkins_ids = checkin::disable_potential_infections(
+            &connection,
+            &infection.places_ids,
+            &infection.start_timestamp,
+            &infection.end_timestamp,
+            minimum_overlap,
+            None,
+        )?;
+
+        notification::insert_retractions(&connection, infection_id, &cleared_checkins_ids)?;
+
+        Ok(())
+    })
+}
+
+fn set_reviewed(
+    connection: &PgConnection,
+    infection_id: &Uuid,
+    reviewer_id: &Uuid,
+    reviewed_statuses: Vec<InfectionStatus>,
+    status: InfectionStatus,
+) -> Result<Infection, Error> {
+    diesel::update(
+        dsl::infection.filter(
+            dsl::id
+                .eq(infection_id)
+                .and(dsl::status.eq_any(reviewed_statuses)),
+        ),
+    )
+    .set((
+        dsl::status.eq(status),
+        dsl::reviewed_by.eq(reviewer_id),
+        dsl::reviewed_at.eq(Utc::now()),
+    ))
+    .returning(INFECTION_COLUMNS)
+    .get_result::<Infection>(connection)
+    .map_err(|error| match error {
+        diesel::result::Error::NotFound => Error::NotFoundWithName {
+            name: String::from("Reviewable infection"),
+        },
+        _ => error.into(),
+    })
+}
+
+/// Delete the infection and clear potential infections not covered by another one
+///
+/// Pending warnings are dropped and visitors already warned are optionally
+/// notified of the retraction.
+pub fn delete(
+    connector: &Connector,
+    infection_id: &Uuid,
+    notify_retractions: bool,
+) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+    let minimum_overlap = connector.configuration.infection.minimum_overlap;
+
+    connection.transaction::<(), Error, _>(|| {
+        notification::delete_pending(&connection, infection_id, None)?;
+
+        // Stop covering checkins before the deletion unlinks the warnings sent
+        let previous = diesel::update(dsl::infection.filter(dsl::id.eq(infection_id)))
+            .set(dsl::status.eq(InfectionStatus::Rejected))
+            .returning(INFECTION_COLUMNS)
+            .get_result::<Infection>(&connection)?;
+
+        let cleared_checkins_ids = checkin::disable_potential_infections(
+            &connection,
+            &previous.places_ids,
+            &previous.start_timestamp,
+            &previous.end_timestamp,
+            minimum_overlap,
+            None,
+        )?;
+
+        if notify_retractions {
+            notification::insert_retractions(&connection, infection_id, &cleared_checkins_ids)?;
+        }
 
-    checkin::enable_potential_infections(
-        connector,
-        &infection.places_ids,
-        &infection.start_timestamp,
-        &infection.end_timestamp,
-    )?;
+        let count =
+            diesel::delete(dsl::infection.filter(dsl::id.eq(infection_id))).execute(&connection)?;
 
-    Ok(id)
+        is_one(count, "Infection")
+    })
 }
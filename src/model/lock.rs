@@ -0,0 +1,67 @@
+use super::error::Error;
+use crate::connector::Connector;
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::sql_types::{BigInt, Bool, Timestamptz};
+use tracing::error;
+
+#[derive(QueryableByName)]
+struct AdvisoryLockRow {
+    #[sql_type = "Bool"]
+    locked: bool,
+}
+
+/// Advisory lock shared with every process connected to the same database
+///
+/// The lock is held on a dedicated connection and released when dropped.
+pub struct AdvisoryLock {
+    connection: PooledConnection<ConnectionManager<PgConnection>>,
+    key: i64,
+}
+
+/// Acquire the advisory lock, returning None if already held elsewhere
+pub fn try_advisory_lock(connector: &Connector, key: i64) -> Result<Option<AdvisoryLock>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    let lock = diesel::sql_query("SELECT pg_try_advisory_lock($1) AS locked")
+        .bind::<BigInt, _>(key)
+        .get_result::<AdvisoryLockRow>(&connection)?;
+
+    if lock.locked {
+        Ok(Some(AdvisoryLock { connection, key }))
+    } else {
+        Ok(None)
+    }
+}
+
+impl AdvisoryLock {
+    /// Record the run of a tick, returning false if it already ran on another replica
+    /// whose tick fired earlier
+    pub fn claim_tick(&self, tick_timestamp: &DateTime<Utc>) -> Result<bool, Error> {
+        let count = diesel::sql_query(
+            "INSERT INTO task_run (lock_key, tick_timestamp)
+            VALUES ($1, $2)
+            ON CONFLICT (lock_key) DO UPDATE SET tick_timestamp = EXCLUDED.tick_timestamp
+            WHERE task_run.tick_timestamp < EXCLUDED.tick_timestamp",
+        )
+        .bind::<BigInt, _>(self.key)
+        .bind::<Timestamptz, _>(tick_timestamp)
+        .execute(&self.connection)?;
+
+        Ok(count == 1)
+    }
+}
+
+impl Drop for AdvisoryLock {
+    fn drop(&mut self) {
+        let result = diesel::sql_query("SELECT pg_advisory_unlock($1) AS locked")
+            .bind::<BigInt, _>(self.key)
+            .get_result::<AdvisoryLockRow>(&self.connection);
+
+        if let Err(error) = result {
+            error!("Unable to release advisory lock {}: {}", self.key, error);
+        }
+    }
+}
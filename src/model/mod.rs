@@ -2,9 +2,17 @@ mod schema;
 
 pub mod checkin;
 pub mod error;
+pub mod exposure;
 pub mod infection;
+pub mod lock;
+pub mod notification;
+pub mod occupancy;
+pub mod opening_hour;
 pub mod organization;
 pub mod place;
+pub mod push_subscription;
+pub mod rate_limit;
 pub mod session;
+pub mod statistics;
 pub mod types;
 pub mod user;
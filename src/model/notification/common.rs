@@ -0,0 +1,82 @@
+use super::super::types::{
+    NotificationKind, NotificationStatus, Notification_kind, Notification_status, Timezone,
+};
+use chrono::{DateTime, Utc};
+use diesel::sql_types::{BigInt, Nullable, Text, Timestamptz};
+use uuid::Uuid;
+
+#[derive(QueryableByName)]
+pub struct PendingNotification {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub id: Uuid,
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub user_id: Uuid,
+    #[sql_type = "Notification_kind"]
+    pub kind: NotificationKind,
+    #[sql_type = "BigInt"]
+    pub attempts: i64,
+    #[sql_type = "Text"]
+    pub email: String,
+    /// Language of the recipient
+    #[sql_type = "Text"]
+    pub locale: String,
+    #[sql_type = "Text"]
+    pub organization_name: String,
+    #[sql_type = "Text"]
+    pub place_name: String,
+    #[sql_type = "Text"]
+    pub timezone: Timezone,
+    #[sql_type = "Timestamptz"]
+    pub checkin_start_timestamp: DateTime<Utc>,
+    #[sql_type = "Nullable<BigInt>"]
+    pub overlap_minutes: Option<i64>,
+}
+
+/// Summary of an infection to send to its organization
+#[derive(QueryableByName)]
+pub struct PendingSummary {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub id: Uuid,
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub infection_id: Uuid,
+    #[sql_type = "Text"]
+    pub email: String,
+    /// Language of the recipient
+    #[sql_type = "Text"]
+    pub locale: String,
+    #[sql_type = "Timestamptz"]
+    pub start_timestamp: DateTime<Utc>,
+    #[sql_type = "Timestamptz"]
+    pub end_timestamp: DateTime<Utc>,
+    /// Timezone of the first place of the infection
+    #[sql_type = "Text"]
+    pub timezone: Timezone,
+}
+
+#[derive(QueryableByName)]
+pub struct NotificationStatusCount {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub infection_id: Uuid,
+    #[sql_type = "Notification_status"]
+    pub status: NotificationStatus,
+    #[sql_type = "BigInt"]
+    pub count: i64,
+}
+
+/// Visitors warned of an infection in one of its places
+#[derive(QueryableByName)]
+pub struct PlaceNotificationSummary {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub place_id: Uuid,
+    #[sql_type = "Text"]
+    pub place_name: String,
+    #[sql_type = "BigInt"]
+    pub visitors_count: i64,
+}
+
+#[derive(Clone, Default)]
+pub struct NotificationStats {
+    pub pending: i64,
+    pub sent: i64,
+    pub failed: i64,
+}
@@ -0,0 +1,378 @@
+mod common;
+
+use super::error::Error;
+use crate::connector::Connector;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Nullable, Text};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub use super::types::{NotificationKind, NotificationStatus};
+pub use common::*;
+
+/// Number of attempts before a notification is considered failed
+const MAXIMUM_ATTEMPTS: i64 = 5;
+
+/// Number of recipients, or summaries, whose notifications are loaded at once by the worker
+const BATCH_SIZE: i64 = 100;
+
+/// Queue a warning for each checkin potentially infected and not already warned
+///
+/// The overlap between the checkin and the infection is stored in whole minutes, rounded
+/// down like the flagging of potential infections.
+pub fn insert_warnings(
+    connection: &PgConnection,
+    infection_id: &Uuid,
+    minimum_overlap: i64,
+) -> Result<usize, Error> {
+    diesel::sql_query(
+        r#"
+        INSERT INTO notification (infection_id, checkin_id, user_id, kind, channel, overlap_minutes)
+        SELECT infection.id, checkin.id, checkin.user_id, 'infection_warning', 'email', overlap.minutes
+        FROM infection
+        INNER JOIN checkin ON checkin.place_id = ANY(infection.places_ids)
+            AND checkin.start_timestamp <= infection.end_timestamp
+            AND checkin.end_timestamp >= infection.start_timestamp
+        INNER JOIN place ON place.id = checkin.place_id
+        CROSS JOIN LATERAL (
+            SELECT CAST(FLOOR(EXTRACT(EPOCH FROM
+                LEAST(checkin.end_timestamp, infection.end_timestamp)
+                - GREATEST(checkin.start_timestamp, infection.start_timestamp)
+            ) / 60) AS int8) AS minutes
+        ) AS overlap
+        WHERE infection.id = $1
+            AND infection.status = 'approved'
+            AND checkin.user_id IS DISTINCT FROM infection.reported_by
+            AND overlap.minutes >= COALESCE(place.minimum_overlap, $2)
+            AND NOT EXISTS (
+                SELECT 1
+                FROM notification
+                WHERE notification.infection_id = infection.id
+                    AND notification.checkin_id = checkin.id
+                    AND notification.kind = 'infection_warning'
+            )
+        "#,
+    )
+    .bind::<diesel::sql_types::Uuid, _>(infection_id)
+    .bind::<BigInt, _>(minimum_overlap)
+    .execute(connection)
+    .map_err(|error| error.into())
+}
+
+/// Queue a retraction for each checkin already warned of the infection
+pub fn insert_retractions(
+    connection: &PgConnection,
+    infection_id: &Uuid,
+    checkins_ids: &Vec<Uuid>,
+) -> Result<usize, Error> {
+    diesel::sql_query(
+        r#"
+        INSERT INTO notification (infection_id, checkin_id, user_id, kind, channel)
+        SELECT $1, checkin.id, checkin.user_id, 'infection_retraction', 'email'
+        FROM checkin
+        WHERE checkin.id = ANY($2)
+            AND EXISTS (
+                SELECT 1
+                FROM notification
+                WHERE notification.checkin_id = checkin.id
+                    AND notification.infection_id = $1
+                    AND notification.kind = 'infection_warning'
+                    AND notification.status = 'sent'
+            )
+        "#,
+    )
+    .bind::<diesel::sql_types::Uuid, _>(infection_id)
+    .bind::<Array<diesel::sql_types::Uuid>, _>(checkins_ids)
+    .execute(connection)
+    .map_err(|error| error.into())
+}
+
+/// Queue a report of the self-reported infection to the organization owning its place
+///
+/// The report refers to the first checkin of the reporter during the infection.
+pub fn insert_report(connection: &PgConnection, infection_id: &Uuid) -> Result<usize, Error> {
+    diesel::sql_query(
+        r#"
+        INSERT INTO notification (infection_id, checkin_id, user_id, kind, channel)
+        SELECT infection.id, reported.id, organization.user_id, 'infection_report', 'email'
+        FROM infection
+        INNER JOIN organization ON organization.id = infection.organization_id
+        CROSS JOIN LATERAL (
+            SELECT checkin.id
+            FROM checkin
+            WHERE checkin.user_id = infection.reported_by
+                AND checkin.place_id = ANY(infection.places_ids)
+                AND checkin.start_timestamp <= infection.end_timestamp
+                AND checkin.end_timestamp >= infection.start_timestamp
+            ORDER BY checkin.start_timestamp
+            LIMIT 1
+        ) AS reported
+        WHERE infection.id = $1
+        "#,
+    )
+    .bind::<diesel::sql_types::Uuid, _>(infection_id)
+    .execute(connection)
+    .map_err(|error| error.into())
+}
+
+/// Queue a summary of the warnings to the organization owning the infection
+pub fn insert_summary(connection: &PgConnection, infection_id: &Uuid) -> Result<usize, Error> {
+    diesel::sql_query(
+        r#"
+        INSERT INTO notification (infection_id, user_id, kind, channel)
+        SELECT infection.id, organization.user_id, 'infection_summary', 'email'
+        FROM infection
+        INNER JOIN organization ON organization.id = infection.organization_id
+        WHERE infection.id = $1
+            AND NOT EXISTS (
+                SELECT 1
+                FROM notification
+                WHERE notification.infection_id = infection.id
+                    AND notification.kind = 'infection_summary'
+                    AND notification.status = 'pending'
+            )
+        "#,
+    )
+    .bind::<diesel::sql_types::Uuid, _>(infection_id)
+    .execute(connection)
+    .map_err(|error| error.into())
+}
+
+/// Remove warnings and summary of the infection not sent yet, optionally restricted to
+/// the warnings of some checkins
+pub fn delete_pending(
+    connection: &PgConnection,
+    infection_id: &Uuid,
+    checkins_ids: Option<&Vec<Uuid>>,
+) -> Result<usize, Error> {
+    diesel::sql_query(
+        r#"
+        DELETE FROM notification
+        WHERE infection_id = $1
+            AND kind IN ('infection_warning', 'infection_summary')
+            AND status = 'pending'
+            AND ($2 IS NULL OR checkin_id = ANY($2))
+        "#,
+    )
+    .bind::<diesel::sql_types::Uuid, _>(infection_id)
+    .bind::<Nullable<Array<diesel::sql_types::Uuid>>, _>(checkins_ids)
+    .execute(connection)
+    .map_err(|error| error.into())
+}
+
+/// Count visitors warned of an infection by place
+pub fn get_summary_with_infection(
+    connector: &Connector,
+    infection_id: &Uuid,
+) -> Result<Vec<PlaceNotificationSummary>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        r#"
+        SELECT
+            place.id AS place_id,
+            place.name AS place_name,
+            warned.visitors_count
+        FROM infection
+        INNER JOIN place ON place.id = ANY(infection.places_ids)
+        CROSS JOIN LATERAL (
+            SELECT COUNT(DISTINCT notification.user_id) AS visitors_count
+            FROM notification
+            INNER JOIN checkin ON checkin.id = notification.checkin_id
+            WHERE notification.infection_id = infection.id
+                AND notification.kind = 'infection_warning'
+                AND notification.status = 'sent'
+                AND checkin.place_id = place.id
+        ) AS warned
+        WHERE infection.id = $1
+        ORDER BY place.name
+        "#,
+    )
+    .bind::<diesel::sql_types::Uuid, _>(infection_id)
+    .load::<PlaceNotificationSummary>(&connection)
+    .map_err(|error| error.into())
+}
+
+/// Get the notifications due for delivery of the next batch of recipients
+///
+/// Warnings of a user already warned today are delayed until the next day so
+/// that they are sent together. All the due notifications of a recipient are
+/// loaded in the same batch so that its warnings are never split.
+pub fn get_all_due(connector: &Connector) -> Result<Vec<PendingNotification>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        r#"
+        WITH due AS (
+            SELECT notification.id, notification.user_id
+            FROM notification
+            WHERE notification.status = 'pending'
+                AND notification.checkin_id IS NOT NULL
+                AND notification.next_attempt_at <= NOW()
+                AND (
+                    notification.kind <> 'infection_warning'
+                    OR NOT EXISTS (
+                        SELECT 1
+                        FROM notification AS sent
+                        WHERE sent.user_id = notification.user_id
+                            AND sent.kind = 'infection_warning'
+                            AND sent.status = 'sent'
+                            AND sent.sent_at >= DATE_TRUNC('day', NOW())
+                    )
+                )
+        ), due_user AS (
+            SELECT DISTINCT user_id
+            FROM due
+            ORDER BY user_id
+            LIMIT $1
+        )
+        SELECT
+            notification.id,
+            notification.user_id,
+            notification.kind,
+            notification.attempts,
+            "user".email,
+            "user".locale,
+            organization.name AS organization_name,
+            place.name AS place_name,
+            place.timezone,
+            checkin.start_timestamp AS checkin_start_timestamp,
+            notification.overlap_minutes
+        FROM due
+        INNER JOIN due_user ON due_user.user_id = due.user_id
+        INNER JOIN notification ON notification.id = due.id
+        INNER JOIN "user" ON "user".id = notification.user_id
+        INNER JOIN checkin ON checkin.id = notification.checkin_id
+        INNER JOIN place ON place.id = checkin.place_id
+        INNER JOIN organization ON organization.id = place.organization_id
+        ORDER BY notification.user_id, notification.next_attempt_at
+        "#,
+    )
+    .bind::<BigInt, _>(BATCH_SIZE)
+    .load::<PendingNotification>(&connection)
+    .map_err(|error| error.into())
+}
+
+/// Get the next batch of summaries due for delivery
+///
+/// A summary is delayed until no warning of its infection is pending anymore.
+pub fn get_all_due_summaries(connector: &Connector) -> Result<Vec<PendingSummary>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        r#"
+        SELECT
+            notification.id,
+            notification.infection_id,
+            "user".email,
+            "user".locale,
+            infection.start_timestamp,
+            infection.end_timestamp,
+            first_place.timezone
+        FROM notification
+        INNER JOIN "user" ON "user".id = notification.user_id
+        INNER JOIN infection ON infection.id = notification.infection_id
+        CROSS JOIN LATERAL (
+            SELECT place.timezone
+            FROM place
+            WHERE place.id = ANY(infection.places_ids)
+            ORDER BY place.name
+            LIMIT 1
+        ) AS first_place
+        WHERE notification.kind = 'infection_summary'
+            AND notification.status = 'pending'
+            AND notification.next_attempt_at <= NOW()
+            AND NOT EXISTS (
+                SELECT 1
+                FROM notification AS warning
+                WHERE warning.infection_id = notification.infection_id
+                    AND warning.kind = 'infection_warning'
+                    AND warning.status = 'pending'
+            )
+        ORDER BY notification.next_attempt_at
+        LIMIT $1
+        "#,
+    )
+    .bind::<BigInt, _>(BATCH_SIZE)
+    .load::<PendingSummary>(&connection)
+    .map_err(|error| error.into())
+}
+
+pub fn set_sent(connector: &Connector, ids: &Vec<Uuid>) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        r#"
+        UPDATE notification
+        SET status = 'sent', attempts = attempts + 1, last_error = NULL, sent_at = NOW()
+        WHERE id = ANY($1)
+        "#,
+    )
+    .bind::<Array<diesel::sql_types::Uuid>, _>(ids)
+    .execute(&connection)
+    .map(|_| ())
+    .map_err(|error| error.into())
+}
+
+/// Record a failed attempt, retrying later with an exponential backoff
+pub fn set_attempt_failed(
+    connector: &Connector,
+    ids: &Vec<Uuid>,
+    error: &str,
+) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        r#"
+        UPDATE notification
+        SET attempts = attempts + 1,
+            last_error = $2,
+            status = CASE
+                WHEN attempts + 1 >= $3 THEN 'failed'::notification_status
+                ELSE 'pending'::notification_status
+            END,
+            next_attempt_at = NOW() + INTERVAL '1 minute' * POWER(2, attempts)
+        WHERE id = ANY($1)
+        "#,
+    )
+    .bind::<Array<diesel::sql_types::Uuid>, _>(ids)
+    .bind::<Text, _>(error)
+    .bind::<BigInt, _>(MAXIMUM_ATTEMPTS)
+    .execute(&connection)
+    .map(|_| ())
+    .map_err(|error| error.into())
+}
+
+pub fn get_stats_with_infections(
+    connector: &Connector,
+    infections_ids: &Vec<Uuid>,
+) -> Result<HashMap<Uuid, NotificationStats>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    let counts = diesel::sql_query(
+        r#"
+        SELECT infection_id, status, COUNT(*) AS count
+        FROM notification
+        WHERE infection_id = ANY($1)
+            AND kind <> 'infection_summary'
+        GROUP BY infection_id, status
+        "#,
+    )
+    .bind::<Array<diesel::sql_types::Uuid>, _>(infections_ids)
+    .load::<NotificationStatusCount>(&connection)?;
+
+    let mut stats: HashMap<Uuid, NotificationStats> = HashMap::new();
+
+    for count in counts {
+        let infection_stats = stats.entry(count.infection_id).or_default();
+
+        match count.status {
+            NotificationStatus::Pending => infection_stats.pending = count.count,
+            NotificationStatus::Sent => infection_stats.sent = count.count,
+            NotificationStatus::Failed => infection_stats.failed = count.count,
+        }
+    }
+
+    Ok(stats)
+}
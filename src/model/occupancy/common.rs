@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use diesel::sql_types::*;
+
+pub enum OccupancyBucket {
+    Hour,
+    Day,
+}
+
+impl OccupancyBucket {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            OccupancyBucket::Hour => "hour",
+            OccupancyBucket::Day => "day",
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+pub struct Occupancy {
+    #[sql_type = "Timestamptz"]
+    pub start_timestamp: DateTime<Utc>,
+    #[sql_type = "Timestamptz"]
+    pub end_timestamp: DateTime<Utc>,
+    /// Visitors who checked in during the bucket
+    #[sql_type = "Int8"]
+    pub visitors: i64,
+    /// Most visitors present at once during the bucket
+    #[sql_type = "Int8"]
+    pub peak_gauge: i64,
+    /// Average stay duration in minutes of these visitors
+    #[sql_type = "Nullable<Float8>"]
+    pub average_duration: Option<f64>,
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use super::error::Error;
+use super::types::Timezone;
+use crate::connector::Connector;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::*;
+
+pub use common::*;
+
+/// Occupancy of a place between two timestamps, bucketed in the place timezone
+pub fn get_all_with_place(
+    connector: &Connector,
+    place_id: &uuid::Uuid,
+    timezone: &Timezone,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+    bucket: &OccupancyBucket,
+) -> Result<Vec<Occupancy>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        "WITH bucket AS (
+            SELECT
+                local_start AT TIME ZONE $2 AS start_timestamp,
+                (local_start + ('1 ' || $3)::interval) AT TIME ZONE $2 AS end_timestamp
+            FROM generate_series(
+                date_trunc($3, $4 AT TIME ZONE $2),
+                $5 AT TIME ZONE $2,
+                ('1 ' || $3)::interval
+            ) AS local_start
+            WHERE local_start < $5 AT TIME ZONE $2
+        )
+        SELECT
+            bucket.start_timestamp,
+            bucket.end_timestamp,
+            COALESCE(visit.visitors, 0)::int8 AS visitors,
+            COALESCE(peak.peak_gauge, 0)::int8 AS peak_gauge,
+            visit.average_duration
+        FROM bucket
+        LEFT JOIN LATERAL (
+            SELECT SUM(number) AS visitors, AVG(duration)::float8 AS average_duration
+            FROM checkin
+            WHERE place_id = $1
+                AND confirmed = TRUE
+                AND start_timestamp >= bucket.start_timestamp
+                AND start_timestamp < bucket.end_timestamp
+        ) AS visit ON TRUE
+        LEFT JOIN LATERAL (
+            -- Occupancy only rises when a checkin starts, so the peak is reached at one of them
+            SELECT MAX(active.visitors) AS peak_gauge
+            FROM (
+                SELECT bucket.start_timestamp AS instant
+                UNION
+                SELECT start_timestamp
+                FROM checkin
+                WHERE place_id = $1
+                    AND confirmed = TRUE
+                    AND start_timestamp >= bucket.start_timestamp
+                    AND start_timestamp < bucket.end_timestamp
+            ) AS event
+            CROSS JOIN LATERAL (
+                SELECT SUM(number) AS visitors
+                FROM checkin
+                WHERE place_id = $1
+                    AND confirmed = TRUE
+                    AND start_timestamp <= event.instant
+                    AND end_timestamp > event.instant
+            ) AS active
+        ) AS peak ON TRUE
+        ORDER BY bucket.start_timestamp ASC",
+    )
+    .bind::<diesel::sql_types::Uuid, _>(place_id)
+    .bind::<Text, _>(timezone)
+    .bind::<Text, _>(bucket.as_sql())
+    .bind::<Timestamptz, _>(from)
+    .bind::<Timestamptz, _>(to)
+    .load::<Occupancy>(&connection)
+    .map_err(|error| error.into())
+}
@@ -0,0 +1,99 @@
+use super::super::schema::{opening_hour_computed, opening_hour_date, opening_hour_day};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use uuid::Uuid;
+
+#[derive(Queryable)]
+pub struct OpeningHourDay {
+    pub place_id: Uuid,
+    /// ISO day of week, 1 is Monday and 7 is Sunday
+    pub day: i16,
+    pub opening_time: NaiveTime,
+    pub closure_time: NaiveTime,
+    pub evacuation_time: NaiveTime,
+}
+
+pub type OpeningHourDayColumns = (
+    opening_hour_day::place_id,
+    opening_hour_day::day,
+    opening_hour_day::opening_time,
+    opening_hour_day::closure_time,
+    opening_hour_day::evacuation_time,
+);
+
+/// Columns read into `OpeningHourDay`, in the order of its fields
+pub const OPENING_HOUR_DAY_COLUMNS: OpeningHourDayColumns = (
+    opening_hour_day::place_id,
+    opening_hour_day::day,
+    opening_hour_day::opening_time,
+    opening_hour_day::closure_time,
+    opening_hour_day::evacuation_time,
+);
+
+#[derive(Queryable)]
+pub struct OpeningHourDate {
+    pub place_id: Uuid,
+    pub date: NaiveDate,
+    pub opening_time: Option<NaiveTime>,
+    pub closure_time: Option<NaiveTime>,
+    pub evacuation_time: Option<NaiveTime>,
+    pub closed: bool,
+}
+
+pub type OpeningHourDateColumns = (
+    opening_hour_date::place_id,
+    opening_hour_date::date,
+    opening_hour_date::opening_time,
+    opening_hour_date::closure_time,
+    opening_hour_date::evacuation_time,
+    opening_hour_date::closed,
+);
+
+/// Columns read into `OpeningHourDate`, in the order of its fields
+pub const OPENING_HOUR_DATE_COLUMNS: OpeningHourDateColumns = (
+    opening_hour_date::place_id,
+    opening_hour_date::date,
+    opening_hour_date::opening_time,
+    opening_hour_date::closure_time,
+    opening_hour_date::evacuation_time,
+    opening_hour_date::closed,
+);
+
+#[derive(Insertable)]
+#[table_name = "opening_hour_day"]
+pub struct OpeningHourDayInsert {
+    pub place_id: Uuid,
+    pub day: i16,
+    pub opening_time: NaiveTime,
+    pub closure_time: NaiveTime,
+    pub evacuation_time: NaiveTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "opening_hour_date"]
+pub struct OpeningHourDateInsert {
+    pub place_id: Uuid,
+    pub date: NaiveDate,
+    pub opening_time: Option<NaiveTime>,
+    pub closure_time: Option<NaiveTime>,
+    pub evacuation_time: Option<NaiveTime>,
+    pub closed: bool,
+}
+
+#[derive(Insertable)]
+#[table_name = "opening_hour_computed"]
+pub struct OpeningHourComputedInsert {
+    pub place_id: Uuid,
+    pub opening_timestamp: DateTime<Utc>,
+    pub closing_timestamp: DateTime<Utc>,
+    pub evacuation_timestamp: DateTime<Utc>,
+}
+
+pub enum OpeningStatus {
+    /// No opening hours defined, the place is considered always open
+    Unscheduled,
+    /// Open within an interval whose visitors leave at the evacuation timestamp
+    Open {
+        evacuation_timestamp: DateTime<Utc>,
+    },
+    Closed,
+}
@@ -0,0 +1,353 @@
+mod common;
+
+use super::error::Error;
+use super::schema::{opening_hour_computed, opening_hour_date, opening_hour_day, place};
+use super::types::Timezone;
+use crate::connector::Connector;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const INSERT_CHUNK_SIZE: usize = 1000;
+
+pub use common::*;
+
+pub fn get_all_with_place(
+    connector: &Connector,
+    place_id: &Uuid,
+) -> Result<(Vec<OpeningHourDay>, Vec<OpeningHourDate>), Error> {
+    let connection = connector.local.pool.get()?;
+
+    let days = opening_hour_day::dsl::opening_hour_day
+        .select(OPENING_HOUR_DAY_COLUMNS)
+        .filter(opening_hour_day::dsl::place_id.eq(place_id))
+        .order((
+            opening_hour_day::dsl::day.asc(),
+            opening_hour_day::dsl::opening_time.asc(),
+        ))
+        .load::<OpeningHourDay>(&connection)?;
+
+    let dates = opening_hour_date::dsl::opening_hour_date
+        .select(OPENING_HOUR_DATE_COLUMNS)
+        .filter(opening_hour_date::dsl::place_id.eq(place_id))
+        .order((
+            opening_hour_date::dsl::date.asc(),
+            opening_hour_date::dsl::opening_time.asc(),
+        ))
+        .load::<OpeningHourDate>(&connection)?;
+
+    Ok((days, dates))
+}
+
+pub fn replace_all_with_place(
+    connector: &Connector,
+    place_id: &Uuid,
+    days: &Vec<OpeningHourDayInsert>,
+    dates: &Vec<OpeningHourDateInsert>,
+) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    connection.transaction::<(), Error, _>(|| {
+        diesel::delete(
+            opening_hour_day::dsl::opening_hour_day
+                .filter(opening_hour_day::dsl::place_id.eq(place_id)),
+        )
+        .execute(&connection)?;
+
+        diesel::delete(
+            opening_hour_date::dsl::opening_hour_date
+                .filter(opening_hour_date::dsl::place_id.eq(place_id)),
+        )
+        .execute(&connection)?;
+
+        diesel::insert_into(opening_hour_day::dsl::opening_hour_day)
+            .values(days)
+            .execute(&connection)?;
+
+        diesel::insert_into(opening_hour_date::dsl::opening_hour_date)
+            .values(dates)
+            .execute(&connection)?;
+
+        Ok(())
+    })
+}
+
+pub fn get_status(
+    connector: &Connector,
+    place_id: &Uuid,
+    timestamp: &DateTime<Utc>,
+) -> Result<OpeningStatus, Error> {
+    let connection = connector.local.pool.get()?;
+
+    let current = opening_hour_computed::dsl::opening_hour_computed
+        .select(opening_hour_computed::dsl::evacuation_timestamp)
+        .filter(
+            opening_hour_computed::dsl::place_id
+                .eq(place_id)
+                .and(opening_hour_computed::dsl::opening_timestamp.le(timestamp))
+                .and(opening_hour_computed::dsl::closing_timestamp.gt(timestamp)),
+        )
+        .order(opening_hour_computed::dsl::evacuation_timestamp.desc())
+        .first::<DateTime<Utc>>(&connection)
+        .optional()?;
+
+    if let Some(evacuation_timestamp) = current {
+        return Ok(OpeningStatus::Open {
+            evacuation_timestamp,
+        });
+    }
+
+    // Past intervals only mean the computation is exhausted, not that the place is closed
+    let scheduled: i64 = opening_hour_computed::dsl::opening_hour_computed
+        .select(diesel::dsl::count(opening_hour_computed::dsl::id))
+        .filter(
+            opening_hour_computed::dsl::place_id
+                .eq(place_id)
+                .and(opening_hour_computed::dsl::closing_timestamp.gt(timestamp)),
+        )
+        .first(&connection)?;
+
+    if scheduled > 0 {
+        Ok(OpeningStatus::Closed)
+    } else {
+        Ok(OpeningStatus::Unscheduled)
+    }
+}
+
+/// Recompute the intervals of every enabled place, each one in its own transaction
+pub fn refresh_all_computed(connector: &Connector) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+
+    let places_ids = place::dsl::place
+        .select(place::dsl::id)
+        .filter(place::dsl::disabled.eq(false))
+        .load::<Uuid>(&connection)?;
+
+    // Intervals of disabled places are no longer refreshed
+    diesel::delete(
+        opening_hour_computed::dsl::opening_hour_computed.filter(
+            opening_hour_computed::dsl::place_id.eq_any(
+                place::dsl::place
+                    .select(place::dsl::id)
+                    .filter(place::dsl::disabled.eq(true)),
+            ),
+        ),
+    )
+    .execute(&connection)?;
+
+    places_ids
+        .iter()
+        .map(|place_id| {
+            refresh_computed(
+                &connection,
+                place_id,
+                connector.configuration.opening_hours.computed_days,
+            )
+        })
+        .sum()
+}
+
+pub fn refresh_computed_with_place(connector: &Connector, place_id: &Uuid) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+
+    refresh_computed(
+        &connection,
+        place_id,
+        connector.configuration.opening_hours.computed_days,
+    )
+}
+
+/// Rewrite the intervals of the place while holding its lock, so that concurrent
+/// refreshes cannot replace them with intervals computed from stale opening hours
+fn refresh_computed(
+    connection: &PgConnection,
+    place_id: &Uuid,
+    computed_days: i64,
+) -> Result<usize, Error> {
+    connection.transaction::<usize, Error, _>(|| {
+        let places = place::dsl::place
+            .select((place::dsl::id, place::dsl::timezone))
+            .filter(place::dsl::id.eq(place_id))
+            .for_update()
+            .load::<(Uuid, Timezone)>(connection)?;
+
+        let days = opening_hour_day::dsl::opening_hour_day
+            .select(OPENING_HOUR_DAY_COLUMNS)
+            .filter(opening_hour_day::dsl::place_id.eq(place_id))
+            .load::<OpeningHourDay>(connection)?;
+
+        let dates = opening_hour_date::dsl::opening_hour_date
+            .select(OPENING_HOUR_DATE_COLUMNS)
+            .filter(opening_hour_date::dsl::place_id.eq(place_id))
+            .load::<OpeningHourDate>(connection)?;
+
+        let computed = compute(places, days, dates, computed_days);
+
+        diesel::delete(
+            opening_hour_computed::dsl::opening_hour_computed
+                .filter(opening_hour_computed::dsl::place_id.eq(place_id)),
+        )
+        .execute(connection)?;
+
+        insert_computed(connection, &computed)
+    })
+}
+
+fn insert_computed(
+    connection: &PgConnection,
+    computed: &[OpeningHourComputedInsert],
+) -> Result<usize, Error> {
+    computed
+        .chunks(INSERT_CHUNK_SIZE)
+        .map(|chunk| {
+            diesel::insert_into(opening_hour_computed::dsl::opening_hour_computed)
+                .values(chunk)
+                .execute(connection)
+                .map_err(|error| error.into())
+        })
+        .sum()
+}
+
+/// Expand weekly rules and dated overrides of each place into UTC intervals,
+/// starting yesterday to keep intervals running past midnight
+fn compute(
+    places: Vec<(Uuid, Timezone)>,
+    days: Vec<OpeningHourDay>,
+    dates: Vec<OpeningHourDate>,
+    computed_days: i64,
+) -> Vec<OpeningHourComputedInsert> {
+    let mut days_by_place: HashMap<Uuid, Vec<OpeningHourDay>> = HashMap::new();
+    for day in days {
+        days_by_place.entry(day.place_id).or_default().push(day);
+    }
+
+    let mut dates_by_place: HashMap<Uuid, HashMap<NaiveDate, Vec<OpeningHourDate>>> =
+        HashMap::new();
+    for date in dates {
+        dates_by_place
+            .entry(date.place_id)
+            .or_default()
+            .entry(date.date)
+            .or_default()
+            .push(date);
+    }
+
+    let now = Utc::now();
+    let mut computed = Vec::new();
+
+    for (place_id, timezone) in places {
+        let place_days = days_by_place.remove(&place_id).unwrap_or_default();
+        let place_dates = dates_by_place.remove(&place_id).unwrap_or_default();
+        let today = now.with_timezone(&timezone.tz).date().naive_local();
+
+        for offset in -1..computed_days {
+            let date = today + Duration::days(offset);
+
+            // Dated overrides replace the weekly schedule for the whole day
+            let spans: Vec<(NaiveTime, NaiveTime, NaiveTime)> = match place_dates.get(&date) {
+                Some(overrides) => overrides
+                    .iter()
+                    .filter(|o| !o.closed)
+                    .filter_map(
+                        |o| match (o.opening_time, o.closure_time, o.evacuation_time) {
+                            (Some(opening), Some(closure), Some(evacuation)) => {
+                                Some((opening, closure, evacuation))
+                            }
+                            _ => None,
+                        },
+                    )
+                    .collect(),
+                None => place_days
+                    .iter()
+                    .filter(|d| d.day as u32 == date.weekday().number_from_monday())
+                    .map(|d| (d.opening_time, d.closure_time, d.evacuation_time))
+                    .collect(),
+            };
+
+            for (opening_time, closure_time, evacuation_time) in spans {
+                let (open_duration, evacuation_delay) =
+                    time_span(&opening_time, &closure_time, &evacuation_time);
+
+                // Durations are added to local wall-clock time before resolving the timezone
+                let opening = date.and_time(opening_time);
+                let closing = opening + open_duration;
+                let evacuation = closing + evacuation_delay;
+
+                if let (
+                    Some(opening_timestamp),
+                    Some(closing_timestamp),
+                    Some(evacuation_timestamp),
+                ) = (
+                    local_to_utc(&timezone.tz, &opening, false),
+                    local_to_utc(&timezone.tz, &closing, true),
+                    local_to_utc(&timezone.tz, &evacuation, true),
+                ) {
+                    computed.push(OpeningHourComputedInsert {
+                        place_id,
+                        opening_timestamp,
+                        closing_timestamp,
+                        evacuation_timestamp,
+                    });
+                }
+            }
+        }
+    }
+
+    computed
+}
+
+/// Resolve a local time, ambiguous times pick the earliest or latest instant and
+/// times skipped by a DST transition are moved after the transition
+fn local_to_utc(tz: &Tz, local: &NaiveDateTime, latest: bool) -> Option<DateTime<Utc>> {
+    let resolved = tz.from_local_datetime(local);
+
+    let resolved = if latest {
+        resolved.latest()
+    } else {
+        resolved.earliest()
+    };
+
+    resolved
+        .or_else(|| {
+            tz.from_local_datetime(&(*local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+/// Durations of the opening and of the evacuation delay, a closure or an evacuation
+/// time before the opening time being on the next day
+pub fn time_span(
+    opening_time: &NaiveTime,
+    closure_time: &NaiveTime,
+    evacuation_time: &NaiveTime,
+) -> (Duration, Duration) {
+    let open_duration = positive_time_difference(closure_time, opening_time);
+    let evacuation_delay = positive_time_difference(evacuation_time, closure_time);
+
+    (open_duration, evacuation_delay)
+}
+
+/// A span is valid when the place is open for some time and evacuated before opening again
+pub fn is_valid_time_span(
+    opening_time: &NaiveTime,
+    closure_time: &NaiveTime,
+    evacuation_time: &NaiveTime,
+) -> bool {
+    let (open_duration, evacuation_delay) = time_span(opening_time, closure_time, evacuation_time);
+
+    open_duration > Duration::zero() && open_duration + evacuation_delay < Duration::days(1)
+}
+
+fn positive_time_difference(end: &NaiveTime, start: &NaiveTime) -> Duration {
+    let difference = *end - *start;
+
+    if difference < Duration::zero() {
+        difference + Duration::days(1)
+    } else {
+        difference
+    }
+}
@@ -1,18 +1,17 @@
 use super::super::schema::organization;
-use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 #[derive(Queryable)]
 pub struct Organization {
     pub id: Uuid,
-    pub user_id: Uuid,
     pub name: String,
-    pub confirmed: bool,
-    pub disabled: bool,
-    pub updated_at: DateTime<Utc>,
-    pub created_at: DateTime<Utc>,
 }
 
+pub type OrganizationColumns = (organization::id, organization::name);
+
+/// Columns read into `Organization`, in the order of its fields
+pub const ORGANIZATION_COLUMNS: OrganizationColumns = (organization::id, organization::name);
+
 #[derive(Insertable)]
 #[table_name = "organization"]
 pub struct OrganizationUpsert {
@@ -12,13 +12,9 @@ use postgis_diesel::*;
 #[derive(Queryable)]
 pub struct Place {
     pub id: uuid::Uuid,
-    pub organization_id: uuid::Uuid,
     pub name: String,
     pub description: Option<String>,
     pub average_duration: i64,
-    pub disabled: bool,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
     pub maximum_gauge: Option<i64>,
     pub address: Option<String>,
     pub maximum_duration: i64,
@@ -27,10 +23,55 @@ pub struct Place {
     pub current_gauge_level: GaugeLevel,
     pub current_gauge_percent: Option<i64>,
     pub timezone: Timezone,
+    pub warning_percent: Option<i64>,
+    pub alert_percent: Option<i64>,
+    pub minimum_overlap: Option<i64>,
+    pub region: Option<String>,
 }
 
+pub type PlaceColumns = (
+    place::id,
+    place::name,
+    place::description,
+    place::average_duration,
+    place::maximum_gauge,
+    place::address,
+    place::maximum_duration,
+    place::current_gauge,
+    place::location,
+    place::current_gauge_level,
+    place::current_gauge_percent,
+    place::timezone,
+    place::warning_percent,
+    place::alert_percent,
+    place::minimum_overlap,
+    place::region,
+);
+
+/// Columns read into `Place`, in the order of its fields
+pub const PLACE_COLUMNS: PlaceColumns = (
+    place::id,
+    place::name,
+    place::description,
+    place::average_duration,
+    place::maximum_gauge,
+    place::address,
+    place::maximum_duration,
+    place::current_gauge,
+    place::location,
+    place::current_gauge_level,
+    place::current_gauge_percent,
+    place::timezone,
+    place::warning_percent,
+    place::alert_percent,
+    place::minimum_overlap,
+    place::region,
+);
+
 pub struct PlaceSearchResult {
     pub meter_distance: f64,
+    pub opening_timestamp: Option<DateTime<Utc>>,
+    pub closing_timestamp: Option<DateTime<Utc>>,
     pub place: Place,
     pub organization: Organization,
 }
@@ -47,6 +88,9 @@ pub struct PlaceInsert {
     pub maximum_duration: i64,
     pub location: Option<PointC<Point>>,
     pub timezone: Timezone,
+    pub warning_percent: Option<i64>,
+    pub alert_percent: Option<i64>,
+    pub minimum_overlap: Option<i64>,
 }
 
 #[derive(AsChangeset)]
@@ -61,6 +105,25 @@ pub struct PlaceUpdate {
     pub maximum_duration: i64,
     pub location: Option<PointC<Point>>,
     pub timezone: Timezone,
+    pub warning_percent: Option<i64>,
+    pub alert_percent: Option<i64>,
+    pub minimum_overlap: Option<i64>,
+}
+
+#[derive(QueryableByName)]
+pub struct PlaceGaugeChange {
+    #[sql_type = "Uuid"]
+    pub id: uuid::Uuid,
+    #[sql_type = "Int8"]
+    pub previous_gauge: i64,
+    #[sql_type = "Int8"]
+    pub current_gauge: i64,
+}
+
+#[derive(QueryableByName)]
+pub struct ActiveVisitorsRow {
+    #[sql_type = "Int8"]
+    pub count: i64,
 }
 
 #[derive(QueryableByName)]
@@ -68,20 +131,12 @@ pub struct PlaceSearchRow {
     // place table
     #[sql_type = "Uuid"]
     pub id: uuid::Uuid,
-    #[sql_type = "Uuid"]
-    pub organization_id: uuid::Uuid,
     #[sql_type = "Text"]
     pub name: String,
     #[sql_type = "Nullable<Text>"]
     pub description: Option<String>,
     #[sql_type = "Int8"]
     pub average_duration: i64,
-    #[sql_type = "Bool"]
-    pub disabled: bool,
-    #[sql_type = "Timestamptz"]
-    pub created_at: DateTime<Utc>,
-    #[sql_type = "Timestamptz"]
-    pub updated_at: DateTime<Utc>,
     #[sql_type = "Nullable<Int8>"]
     pub maximum_gauge: Option<i64>,
     #[sql_type = "Nullable<Text>"]
@@ -98,41 +153,43 @@ pub struct PlaceSearchRow {
     pub current_gauge_percent: Option<i64>,
     #[sql_type = "Text"]
     pub timezone: Timezone,
+    #[sql_type = "Nullable<Int8>"]
+    pub warning_percent: Option<i64>,
+    #[sql_type = "Nullable<Int8>"]
+    pub alert_percent: Option<i64>,
+    #[sql_type = "Nullable<Int8>"]
+    pub minimum_overlap: Option<i64>,
+    #[sql_type = "Nullable<Text>"]
+    pub region: Option<String>,
 
     // organization table
     #[sql_type = "Uuid"]
     pub org_id: uuid::Uuid,
-    #[sql_type = "Uuid"]
-    pub org_user_id: uuid::Uuid,
     #[sql_type = "Text"]
     pub org_name: String,
-    #[sql_type = "Bool"]
-    pub org_confirmed: bool,
-    #[sql_type = "Bool"]
-    pub org_disabled: bool,
-    #[sql_type = "Timestamptz"]
-    pub org_updated_at: DateTime<Utc>,
-    #[sql_type = "Timestamptz"]
-    pub org_created_at: DateTime<Utc>,
 
     // distance
     #[sql_type = "Float8"]
     pub meter_distance: f64,
+
+    // current or next opening
+    #[sql_type = "Nullable<Timestamptz>"]
+    pub opening_timestamp: Option<DateTime<Utc>>,
+    #[sql_type = "Nullable<Timestamptz>"]
+    pub closing_timestamp: Option<DateTime<Utc>>,
 }
 
 impl From<PlaceSearchRow> for PlaceSearchResult {
     fn from(place_row: PlaceSearchRow) -> Self {
         PlaceSearchResult {
             meter_distance: place_row.meter_distance,
+            opening_timestamp: place_row.opening_timestamp,
+            closing_timestamp: place_row.closing_timestamp,
             place: Place {
                 id: place_row.id,
-                organization_id: place_row.organization_id,
                 name: place_row.name,
                 description: place_row.description,
                 average_duration: place_row.average_duration,
-                disabled: place_row.disabled,
-                created_at: place_row.created_at,
-                updated_at: place_row.updated_at,
                 maximum_gauge: place_row.maximum_gauge,
                 address: place_row.address,
                 maximum_duration: place_row.maximum_duration,
@@ -141,15 +198,14 @@ impl From<PlaceSearchRow> for PlaceSearchResult {
                 current_gauge_level: place_row.current_gauge_level,
                 current_gauge_percent: place_row.current_gauge_percent,
                 timezone: place_row.timezone,
+                warning_percent: place_row.warning_percent,
+                alert_percent: place_row.alert_percent,
+                minimum_overlap: place_row.minimum_overlap,
+                region: place_row.region,
             },
             organization: Organization {
                 id: place_row.org_id,
-                user_id: place_row.org_user_id,
                 name: place_row.org_name,
-                confirmed: place_row.org_confirmed,
-                disabled: place_row.org_disabled,
-                updated_at: place_row.org_updated_at,
-                created_at: place_row.org_created_at,
             },
         }
     }
@@ -1,11 +1,13 @@
 mod common;
 
 use super::error::{is_one, Error};
-use super::organization::Organization;
+use super::organization::{Organization, ORGANIZATION_COLUMNS};
 use super::schema::{organization, place::dsl};
 use super::types::*;
-use crate::connector::Connector;
+use crate::connector::{configuration::GaugeConfiguration, Connector};
 use crate::types::{Pagination, PaginationQuery};
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::sql_types::*;
 use postgis::ewkb::Point;
@@ -18,64 +20,192 @@ pub fn get(connector: &Connector, id: &Uuid) -> Result<Place, Error> {
     let connection = connector.local.pool.get()?;
 
     dsl::place
+        .select(PLACE_COLUMNS)
         .filter(dsl::id.eq(id).and(dsl::disabled.eq(false)))
         .first::<Place>(&connection)
         .map_err(|error| error.into())
 }
 
-pub fn refresh_all_gauges(connector: &Connector) -> Result<usize, Error> {
+/// Lock the place until the end of the current transaction
+pub fn get_for_update(connection: &PgConnection, id: &Uuid) -> Result<Place, Error> {
+    dsl::place
+        .select(PLACE_COLUMNS)
+        .filter(dsl::id.eq(id).and(dsl::disabled.eq(false)))
+        .for_update()
+        .first::<Place>(connection)
+        .map_err(|error| error.into())
+}
+
+/// Count visitors of active checkins, as reconciled by the gauge refresh
+pub fn count_active_visitors(connection: &PgConnection, id: &Uuid) -> Result<i64, Error> {
+    diesel::sql_query(
+        "SELECT COALESCE(SUM(number), 0)::int8 AS count
+        FROM checkin
+        WHERE place_id = $1 AND start_timestamp <= NOW() AND end_timestamp >= NOW()",
+    )
+    .bind::<diesel::sql_types::Uuid, _>(id)
+    .get_result::<ActiveVisitorsRow>(connection)
+    .map(|row| row.count)
+    .map_err(|error| error.into())
+}
+
+/// Add visitors to the gauge of a place, to be called within the checkin transaction,
+/// returning whether the gauge changed
+pub fn add_to_gauge(
+    connection: &PgConnection,
+    gauge: &GaugeConfiguration,
+    id: &Uuid,
+    number: i64,
+) -> Result<bool, Error> {
+    let changes = diesel::sql_query(gauge_update_query(
+        "SELECT
+            id,
+            current_gauge AS previous_gauge,
+            GREATEST(current_gauge + $3, 0) AS current_gauge,
+            maximum_gauge,
+            warning_percent,
+            alert_percent
+        FROM place
+        WHERE id = $4 AND disabled = FALSE",
+    ))
+    .bind::<BigInt, _>(gauge.alert)
+    .bind::<BigInt, _>(gauge.warning)
+    .bind::<BigInt, _>(number)
+    .bind::<diesel::sql_types::Uuid, _>(id)
+    .load::<PlaceGaugeChange>(connection)?;
+
+    if changes.is_empty() {
+        return Ok(false);
+    }
+
+    insert_gauge_snapshots(connection, Some(&vec![*id]))?;
+
+    Ok(true)
+}
+
+/// Reconcile all gauges with active checkins, returning the corrected ones
+pub fn refresh_all_gauges(connector: &Connector) -> Result<Vec<PlaceGaugeChange>, Error> {
+    refresh_gauges(connector, None)
+}
+
+pub fn refresh_gauges_with_places(
+    connector: &Connector,
+    places_ids: &Vec<Uuid>,
+) -> Result<Vec<PlaceGaugeChange>, Error> {
+    refresh_gauges(connector, Some(places_ids))
+}
+
+fn refresh_gauges(
+    connector: &Connector,
+    places_ids: Option<&Vec<Uuid>>,
+) -> Result<Vec<PlaceGaugeChange>, Error> {
     let connection = connector.local.pool.get()?;
 
-    connection
-        .transaction::<usize, Error, _>(|| {
-            let updated = diesel::sql_query(
-                "UPDATE place
-                SET current_gauge = checkin.active_count
-                FROM (SELECT place_id, SUM(number) as active_count
-                    FROM checkin
-                    WHERE start_timestamp <= NOW() AND end_timestamp >= NOW()
-                    GROUP BY place_id) as checkin
-                WHERE checkin.place_id = place.id AND disabled = FALSE",
-            )
-            .execute(&connection)?;
-
-            diesel::sql_query(
-                "UPDATE place
-                SET
-                    current_gauge_percent = NULL,
-                    current_gauge_level = 'unknown'
-                WHERE maximum_gauge IS NULL AND disabled = FALSE",
-            )
-            .execute(&connection)?;
-
-            diesel::sql_query(
-                "UPDATE place
-                SET
-                    current_gauge_percent = (current_gauge * 100) / maximum_gauge ,
-                    current_gauge_level = 'safe'
-                WHERE maximum_gauge IS NOT NULL AND disabled = FALSE",
-            )
-            .execute(&connection)?;
+    let changes = connection.transaction::<Vec<PlaceGaugeChange>, Error, _>(|| {
+        let changes = diesel::sql_query(gauge_update_query(
+            "SELECT
+                place.id,
+                place.current_gauge AS previous_gauge,
+                COALESCE(active.count, 0) AS current_gauge,
+                place.maximum_gauge,
+                place.warning_percent,
+                place.alert_percent
+            FROM place
+            LEFT JOIN (
+                SELECT place_id, SUM(number)::int8 AS count
+                FROM checkin
+                WHERE start_timestamp <= NOW() AND end_timestamp >= NOW()
+                GROUP BY place_id
+            ) AS active
+            ON active.place_id = place.id
+            WHERE place.disabled = FALSE AND ($3 IS NULL OR place.id = ANY($3))",
+        ))
+        .bind::<BigInt, _>(connector.configuration.gauge.alert)
+        .bind::<BigInt, _>(connector.configuration.gauge.warning)
+        .bind::<Nullable<Array<diesel::sql_types::Uuid>>, _>(places_ids)
+        .load::<PlaceGaugeChange>(&connection)?;
 
-            diesel::sql_query(
-                "UPDATE place
-                SET current_gauge_level = 'warning'
-                WHERE current_gauge_percent >= $1 AND disabled = FALSE",
-            )
-            .bind::<BigInt, _>(connector.configuration.gauge.warning)
-            .execute(&connection)?;
+        // Record refreshed gauges to build occupancy history
+        insert_gauge_snapshots(&connection, places_ids)?;
 
-            diesel::sql_query(
-                "UPDATE place
-                SET current_gauge_level = 'alert'
-                WHERE current_gauge_percent >= $1 AND disabled = FALSE",
-            )
-            .bind::<BigInt, _>(connector.configuration.gauge.alert)
-            .execute(&connection)?;
+        Ok(changes)
+    })?;
 
-            Ok(updated)
-        })
-        .map_err(|error| error.into())
+    for change in changes.iter() {
+        connector.gauge.publish(change.id);
+    }
+
+    Ok(changes)
+}
+
+/// Snapshot gauges which changed since their last snapshot, or once per hour so that
+/// the gauge at a past time is known even for steady places
+fn insert_gauge_snapshots(
+    connection: &PgConnection,
+    places_ids: Option<&Vec<Uuid>>,
+) -> Result<usize, Error> {
+    diesel::sql_query(
+        "INSERT INTO gauge_snapshot (place_id, gauge)
+        SELECT place.id, place.current_gauge
+        FROM place
+        LEFT JOIN LATERAL (
+            SELECT gauge, created_at
+            FROM gauge_snapshot
+            WHERE place_id = place.id
+            ORDER BY created_at DESC
+            LIMIT 1
+        ) AS last ON TRUE
+        WHERE place.disabled = FALSE
+            AND ($1 IS NULL OR place.id = ANY($1))
+            AND (
+                last.gauge IS DISTINCT FROM place.current_gauge
+                OR last.created_at < date_trunc('hour', NOW())
+            )",
+    )
+    .bind::<Nullable<Array<diesel::sql_types::Uuid>>, _>(places_ids)
+    .execute(connection)
+    .map_err(|error| error.into())
+}
+
+/// Update gauges, percents and levels from a source of new gauges,
+/// $1 and $2 being the default alert and warning percents
+fn gauge_update_query(gauge_source: &str) -> String {
+    format!(
+        "UPDATE place
+        SET
+            current_gauge = computed.current_gauge,
+            current_gauge_percent = computed.current_gauge_percent,
+            current_gauge_level = computed.current_gauge_level
+        FROM (
+            SELECT
+                id,
+                previous_gauge,
+                current_gauge,
+                current_gauge_percent,
+                (CASE
+                    WHEN current_gauge_percent IS NULL THEN 'unknown'
+                    WHEN current_gauge_percent >= COALESCE(alert_percent, $1) THEN 'alert'
+                    WHEN current_gauge_percent >= COALESCE(warning_percent, $2) THEN 'warning'
+                    ELSE 'safe'
+                END)::gauge_level AS current_gauge_level
+            FROM (
+                SELECT
+                    id,
+                    previous_gauge,
+                    current_gauge,
+                    (current_gauge * 100) / maximum_gauge AS current_gauge_percent,
+                    warning_percent,
+                    alert_percent
+                FROM ({}) AS source
+            ) AS gauge
+        ) AS computed
+        WHERE place.id = computed.id
+            AND (place.current_gauge, place.current_gauge_percent, place.current_gauge_level)
+                IS DISTINCT FROM
+                (computed.current_gauge, computed.current_gauge_percent, computed.current_gauge_level)
+        RETURNING place.id, computed.previous_gauge, computed.current_gauge",
+        gauge_source
+    )
 }
 
 pub fn get_with_organization(
@@ -86,6 +216,7 @@ pub fn get_with_organization(
 
     dsl::place
         .inner_join(organization::dsl::organization)
+        .select((PLACE_COLUMNS, ORGANIZATION_COLUMNS))
         .filter(dsl::id.eq(id).and(dsl::disabled.eq(false)))
         .first::<(Place, Organization)>(&connection)
         .map_err(|error| error.into())
@@ -99,6 +230,7 @@ pub fn get_all_with_organization(
 
     dsl::place
         .inner_join(organization::dsl::organization)
+        .select((PLACE_COLUMNS, ORGANIZATION_COLUMNS))
         .filter(
             organization::dsl::id
                 .eq(organization_id)
@@ -114,6 +246,7 @@ pub fn search(
     location: PointC<Point>,
     radius_in_meters: i64,
     gauge_levels: Vec<GaugeLevel>,
+    open_at: Option<DateTime<Utc>>,
     pagination: PaginationQuery,
 ) -> Result<(Pagination, Vec<PlaceSearchResult>), Error> {
     let connection = connector.local.pool.get()?;
@@ -124,13 +257,9 @@ pub fn search(
             SELECT ST_SetSRID(ST_MakePoint($1, $2), 4326)::geography AS center
         )
         SELECT place.id,
-            place.organization_id,
             place.name,
             place.description,
             place.average_duration,
-            place.disabled,
-            place.created_at,
-            place.updated_at,
             place.maximum_gauge,
             place.address,
             place.maximum_duration,
@@ -138,18 +267,27 @@ pub fn search(
             place.location,
             place.current_gauge_level,
             place.current_gauge_percent,
+            place.timezone,
+            place.warning_percent,
+            place.alert_percent,
+            place.minimum_overlap,
+            place.region,
             organization.id AS org_id,
-            organization.user_id AS org_user_id,
             organization.name AS org_name,
-            organization.confirmed AS org_confirmed,
-            organization.disabled AS org_disabled,
-            organization.updated_at AS org_updated_at,
-            organization.created_at AS org_created_at,
-            ST_Distance(place.location, c.center, false) AS meter_distance
+            ST_Distance(place.location, c.center, false) AS meter_distance,
+            opening.opening_timestamp,
+            opening.closing_timestamp
         FROM place
         JOIN c ON TRUE
         INNER JOIN organization
         ON place.organization_id = organization.id
+        LEFT JOIN LATERAL (
+            SELECT opening_timestamp, closing_timestamp
+            FROM opening_hour_computed
+            WHERE place_id = place.id AND closing_timestamp > NOW()
+            ORDER BY closing_timestamp ASC
+            LIMIT 1
+        ) AS opening ON TRUE
         WHERE
             place.disabled = FALSE
             AND current_gauge_level IN ({})
@@ -159,6 +297,18 @@ pub fn search(
                 $3,
                 false
             )
+            AND (
+                $6 IS NULL
+                OR EXISTS (
+                    SELECT 1 FROM opening_hour_computed
+                    WHERE place_id = place.id
+                        AND opening_timestamp <= $6 AND closing_timestamp > $6
+                )
+                OR NOT EXISTS (
+                    SELECT 1 FROM opening_hour_computed
+                    WHERE place_id = place.id AND closing_timestamp > $6
+                )
+            )
         ORDER BY meter_distance ASC
         LIMIT $4 OFFSET $5",
         join_gauge_levels(gauge_levels)
@@ -168,6 +318,7 @@ pub fn search(
     .bind::<BigInt, _>(radius_in_meters)
     .bind::<BigInt, _>(pagination.limit + 1)
     .bind::<BigInt, _>((pagination.page - 1) * pagination.limit)
+    .bind::<Nullable<Timestamptz>, _>(open_at)
     .load::<PlaceSearchRow>(&connection)?;
 
     let next_page = if places.len() as i64 == pagination.limit + 1 {
@@ -186,6 +337,16 @@ pub fn search(
     ))
 }
 
+pub fn get_ids_with_region(connector: &Connector, region: &String) -> Result<Vec<Uuid>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    dsl::place
+        .select(dsl::id)
+        .filter(dsl::region.eq(region))
+        .load::<Uuid>(&connection)
+        .map_err(|error| error.into())
+}
+
 pub fn validate_places_owned(
     connector: &Connector,
     organization_id: &Uuid,
@@ -246,6 +407,17 @@ pub fn update(
     .and_then(|count| is_one(count, "Place"))
 }
 
+/// Set the region reviewing infections of the place
+pub fn set_region(connector: &Connector, id: &Uuid, region: Option<String>) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::update(dsl::place.filter(dsl::id.eq(id).and(dsl::disabled.eq(false))))
+        .set(dsl::region.eq(region))
+        .execute(&connection)
+        .map_err(|error| error.into())
+        .and_then(|count| is_one(count, "Place"))
+}
+
 pub fn set_disabled(
     connector: &Connector,
     id: &Uuid,
@@ -267,3 +439,16 @@ pub fn set_disabled(
     .map_err(|error| error.into())
     .and_then(|count| is_one(count, "Place"))
 }
+
+/// Delete gauge snapshots older than their retention
+pub fn delete_all_expired_gauge_snapshots(connector: &Connector) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        "DELETE FROM gauge_snapshot
+        WHERE created_at < NOW() - INTERVAL '1 day' * $1",
+    )
+    .bind::<BigInt, _>(connector.configuration.gauge.snapshot_retention_days)
+    .execute(&connection)
+    .map_err(|error| error.into())
+}
@@ -0,0 +1,40 @@
+use super::super::schema::push_subscription;
+use uuid::Uuid;
+
+#[derive(Queryable)]
+pub struct PushSubscription {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub endpoint: String,
+    /// Public key of the browser, base64url encoded
+    pub p256dh: String,
+    /// Authentication secret of the browser, base64url encoded
+    pub auth: String,
+}
+
+pub type PushSubscriptionColumns = (
+    push_subscription::id,
+    push_subscription::session_id,
+    push_subscription::endpoint,
+    push_subscription::p256dh,
+    push_subscription::auth,
+);
+
+/// Columns read into `PushSubscription`, in the order of its fields
+pub const PUSH_SUBSCRIPTION_COLUMNS: PushSubscriptionColumns = (
+    push_subscription::id,
+    push_subscription::session_id,
+    push_subscription::endpoint,
+    push_subscription::p256dh,
+    push_subscription::auth,
+);
+
+#[derive(Insertable, AsChangeset)]
+#[table_name = "push_subscription"]
+pub struct PushSubscriptionUpsert {
+    pub session_id: Uuid,
+    pub user_id: Uuid,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+}
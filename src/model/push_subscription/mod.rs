@@ -0,0 +1,100 @@
+mod common;
+
+use super::error::{is_one, Error};
+use super::schema::push_subscription::dsl;
+use crate::connector::Connector;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+pub use common::*;
+
+/// Register the subscription of a session, replacing the previous one
+pub fn upsert(connector: &Connector, subscription: &PushSubscriptionUpsert) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::insert_into(dsl::push_subscription)
+        .values(subscription)
+        .on_conflict(dsl::session_id)
+        .do_update()
+        .set(subscription)
+        .execute(&connection)
+        .map_err(|error| error.into())
+        .and_then(|count| is_one(count, "PushSubscription"))
+}
+
+pub fn get_all_with_user(
+    connector: &Connector,
+    user_id: &Uuid,
+) -> Result<Vec<PushSubscription>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    dsl::push_subscription
+        .select(PUSH_SUBSCRIPTION_COLUMNS)
+        .filter(dsl::user_id.eq(user_id))
+        .load::<PushSubscription>(&connection)
+        .map_err(|error| error.into())
+}
+
+pub fn get_all_with_sessions(
+    connector: &Connector,
+    sessions_ids: &Vec<Uuid>,
+) -> Result<Vec<PushSubscription>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    dsl::push_subscription
+        .select(PUSH_SUBSCRIPTION_COLUMNS)
+        .filter(dsl::session_id.eq_any(sessions_ids))
+        .load::<PushSubscription>(&connection)
+        .map_err(|error| error.into())
+}
+
+pub fn delete(connector: &Connector, id: &Uuid) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::delete(dsl::push_subscription.find(id))
+        .execute(&connection)
+        .map_err(|error| error.into())
+        .map(|_| ())
+}
+
+pub fn delete_with_session(connector: &Connector, session_id: &Uuid) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::delete(dsl::push_subscription.filter(dsl::session_id.eq(session_id)))
+        .execute(&connection)
+        .map_err(|error| error.into())
+        .map(|_| ())
+}
+
+pub fn delete_all_with_user_except_session(
+    connector: &Connector,
+    user_id: &Uuid,
+    except_session_id: &Uuid,
+) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::delete(
+        dsl::push_subscription.filter(
+            dsl::user_id
+                .eq(user_id)
+                .and(dsl::session_id.ne(except_session_id)),
+        ),
+    )
+    .execute(&connection)
+    .map_err(|error| error.into())
+    .map(|_| ())
+}
+
+/// Delete subscriptions of disabled sessions
+pub fn delete_all_disabled(connector: &Connector) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        "DELETE FROM push_subscription
+        USING session
+        WHERE session.id = push_subscription.session_id
+            AND session.disabled = TRUE",
+    )
+    .execute(&connection)
+    .map_err(|error| error.into())
+}
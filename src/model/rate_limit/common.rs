@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+use diesel::sql_types::{Integer, Timestamptz};
+
+/// Counter of a key after a hit, within its current window
+#[derive(QueryableByName)]
+pub struct RateLimitHit {
+    #[sql_type = "Integer"]
+    pub hits: i32,
+    #[sql_type = "Timestamptz"]
+    pub window_start: DateTime<Utc>,
+}
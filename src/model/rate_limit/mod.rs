@@ -0,0 +1,45 @@
+mod common;
+
+use super::error::Error;
+use crate::connector::Connector;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Text};
+
+pub use common::*;
+
+/// Count a hit of the key, starting a new window if the previous one is over
+pub fn hit(connector: &Connector, key: &str) -> Result<RateLimitHit, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        "INSERT INTO rate_limit (key)
+        VALUES ($1)
+        ON CONFLICT (key) DO UPDATE SET
+            hits = CASE
+                WHEN rate_limit.window_start <= NOW() - INTERVAL '1 second' * $2 THEN 1
+                ELSE rate_limit.hits + 1
+            END,
+            window_start = CASE
+                WHEN rate_limit.window_start <= NOW() - INTERVAL '1 second' * $2 THEN NOW()
+                ELSE rate_limit.window_start
+            END
+        RETURNING hits, window_start",
+    )
+    .bind::<Text, _>(key)
+    .bind::<BigInt, _>(connector.configuration.rate_limit.window)
+    .get_result::<RateLimitHit>(&connection)
+    .map_err(|error| error.into())
+}
+
+/// Delete counters whose window is over
+pub fn delete_all_expired(connector: &Connector) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        "DELETE FROM rate_limit
+        WHERE window_start <= NOW() - INTERVAL '1 second' * $1",
+    )
+    .bind::<BigInt, _>(connector.configuration.rate_limit.window)
+    .execute(&connection)
+    .map_err(|error| error.into())
+}
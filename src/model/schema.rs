@@ -18,6 +18,21 @@ table! {
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
         number -> Int8,
+        reminded_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+    use crate::model::types::*;
+
+    gauge_snapshot (id) {
+        id -> Uuid,
+        place_id -> Uuid,
+        gauge -> Int8,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -34,6 +49,33 @@ table! {
         end_timestamp -> Timestamptz,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        status -> Infection_status,
+        reviewed_by -> Nullable<Uuid>,
+        reviewed_at -> Nullable<Timestamptz>,
+        reported_by -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+    use crate::model::types::*;
+
+    notification (id) {
+        id -> Uuid,
+        infection_id -> Nullable<Uuid>,
+        checkin_id -> Nullable<Uuid>,
+        user_id -> Uuid,
+        kind -> Notification_kind,
+        channel -> Notification_channel,
+        status -> Notification_status,
+        attempts -> Int8,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamptz,
+        sent_at -> Nullable<Timestamptz>,
+        overlap_minutes -> Nullable<Int8>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -126,6 +168,39 @@ table! {
         current_gauge_level -> Gauge_level,
         current_gauge_percent -> Nullable<Int8>,
         timezone -> Text,
+        warning_percent -> Nullable<Int8>,
+        alert_percent -> Nullable<Int8>,
+        minimum_overlap -> Nullable<Int8>,
+        region -> Nullable<Text>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+    use crate::model::types::*;
+
+    push_subscription (id) {
+        id -> Uuid,
+        session_id -> Uuid,
+        user_id -> Uuid,
+        endpoint -> Text,
+        p256dh -> Text,
+        auth -> Text,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+    use crate::model::types::*;
+
+    rate_limit (key) {
+        key -> Text,
+        window_start -> Timestamptz,
+        hits -> Int4,
     }
 }
 
@@ -144,6 +219,20 @@ table! {
         disabled -> Bool,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        last_used_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use postgis_diesel::sql_types::*;
+    use crate::model::types::*;
+
+    task_run (lock_key) {
+        lock_key -> Int8,
+        tick_timestamp -> Timestamptz,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -161,28 +250,41 @@ table! {
         disabled -> Bool,
         updated_at -> Timestamptz,
         created_at -> Timestamptz,
+        region -> Nullable<Text>,
+        locale -> Text,
     }
 }
 
 joinable!(checkin -> place (place_id));
 joinable!(checkin -> session (session_id));
 joinable!(checkin -> user (user_id));
+joinable!(gauge_snapshot -> place (place_id));
 joinable!(infection -> organization (organization_id));
+joinable!(notification -> checkin (checkin_id));
+joinable!(notification -> infection (infection_id));
+joinable!(notification -> user (user_id));
 joinable!(opening_hour_computed -> place (place_id));
 joinable!(opening_hour_date -> place (place_id));
 joinable!(opening_hour_day -> place (place_id));
 joinable!(organization -> user (user_id));
 joinable!(place -> organization (organization_id));
+joinable!(push_subscription -> session (session_id));
+joinable!(push_subscription -> user (user_id));
 joinable!(session -> user (user_id));
 
 allow_tables_to_appear_in_same_query!(
     checkin,
+    gauge_snapshot,
     infection,
+    notification,
     opening_hour_computed,
     opening_hour_date,
     opening_hour_day,
     organization,
     place,
+    push_subscription,
+    rate_limit,
     session,
+    task_run,
     user,
 );
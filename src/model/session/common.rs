@@ -10,11 +10,33 @@ pub struct Session {
     pub hashed_token: Option<String>,
     pub hashed_confirmation_token: Option<String>,
     pub confirmed: bool,
-    pub disabled: bool,
-    pub updated_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
 }
 
+pub type SessionColumns = (
+    session::id,
+    session::user_id,
+    session::description,
+    session::hashed_token,
+    session::hashed_confirmation_token,
+    session::confirmed,
+    session::created_at,
+    session::last_used_at,
+);
+
+/// Columns read into `Session`, in the order of its fields
+pub const SESSION_COLUMNS: SessionColumns = (
+    session::id,
+    session::user_id,
+    session::description,
+    session::hashed_token,
+    session::hashed_confirmation_token,
+    session::confirmed,
+    session::created_at,
+    session::last_used_at,
+);
+
 #[derive(Insertable)]
 #[table_name = "session"]
 pub struct SessionInsert {
@@ -30,4 +52,5 @@ pub struct SessionTokenUpdate {
     pub hashed_confirmation_token: Option<String>,
     pub hashed_token: Option<String>,
     pub confirmed: bool,
+    pub last_used_at: Option<DateTime<Utc>>,
 }
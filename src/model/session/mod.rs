@@ -2,73 +2,92 @@ mod common;
 
 use super::error::{is_one, Error};
 use super::schema::{session::dsl, user};
-use super::user::User;
+use super::user::{User, USER_COLUMNS};
 use crate::connector::Connector;
+use chrono::{Duration, Utc};
 use diesel::prelude::*;
 use uuid::Uuid;
 
 pub use common::*;
 
-pub fn get_unconfirmed(
-    connector: &Connector,
-    id: &Uuid,
-    hashed_confirmation_token: &String,
-) -> Result<(Session, User), Error> {
+/// Get an unconfirmed session whose confirmation link has not expired
+pub fn get_unconfirmed(connector: &Connector, id: &Uuid) -> Result<(Session, User), Error> {
     let connection = connector.local.pool.get()?;
+    let confirmation_limit =
+        Utc::now() - Duration::minutes(connector.configuration.session.confirmation_ttl);
 
     dsl::session
         .inner_join(user::dsl::user)
+        .select((SESSION_COLUMNS, USER_COLUMNS))
         .filter(
             dsl::id
                 .eq(id)
-                .and(dsl::hashed_confirmation_token.eq(hashed_confirmation_token))
                 .and(dsl::confirmed.eq(false))
-                .and(dsl::disabled.eq(false)),
+                .and(dsl::disabled.eq(false))
+                .and(dsl::created_at.gt(confirmation_limit)),
         )
         .first::<(Session, User)>(&connection)
         .map_err(|error| error.into())
 }
 
-pub fn get_confirmed(
-    connector: &Connector,
-    id: &Uuid,
-    hashed_token: &String,
-) -> Result<Option<Session>, Error> {
+/// Get a confirmed session neither idle nor older than their lifetimes
+pub fn get_confirmed(connector: &Connector, id: &Uuid) -> Result<Option<Session>, Error> {
     let connection = connector.local.pool.get()?;
+    let now = Utc::now();
+    let configuration = &connector.configuration.session;
 
     dsl::session
+        .select(SESSION_COLUMNS)
         .filter(
             dsl::id
                 .eq(id)
-                .and(dsl::hashed_token.eq(hashed_token))
                 .and(dsl::confirmed.eq(true))
-                .and(dsl::disabled.eq(false)),
+                .and(dsl::disabled.eq(false))
+                .and(dsl::last_used_at.gt(now - Duration::days(configuration.idle_ttl)))
+                .and(dsl::created_at.gt(now - Duration::days(configuration.absolute_ttl))),
         )
         .first::<Session>(&connection)
         .optional()
         .map_err(|error| error.into())
 }
 
-pub fn confirm(connector: &Connector, id: &Uuid, hashed_token: &String) -> Result<(), Error> {
+pub fn confirm(connector: &Connector, id: &Uuid, hashed_token: &str) -> Result<(), Error> {
     let connection = connector.local.pool.get()?;
 
     diesel::update(dsl::session.find(id))
         .set(&SessionTokenUpdate {
             hashed_confirmation_token: None,
-            hashed_token: Some(hashed_token.clone()),
+            hashed_token: Some(hashed_token.to_string()),
             confirmed: true,
+            last_used_at: Some(Utc::now()),
         })
         .execute(&connection)
         .map_err(|error| error.into())
         .and_then(|count| is_one(count, "Session"))
 }
 
+/// Replace the token hash, upgrading a legacy one
+pub fn set_hashed_token(
+    connector: &Connector,
+    id: &Uuid,
+    hashed_token: &String,
+) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::update(dsl::session.find(id))
+        .set(dsl::hashed_token.eq(hashed_token))
+        .execute(&connection)
+        .map_err(|error| error.into())
+        .and_then(|count| is_one(count, "Session"))
+}
+
 pub fn insert(connector: &Connector, session: &SessionInsert) -> Result<Session, Error> {
     let connection = connector.local.pool.get()?;
 
     // Insert user if not exists, otherwise update its email which the unhashed version of the login
     diesel::insert_into(dsl::session)
         .values(session)
+        .returning(SESSION_COLUMNS)
         .get_result(&connection)
         .map_err(|error| error.into())
 }
@@ -83,3 +102,94 @@ pub fn set_disabled(connector: &Connector, id: &Uuid, disabled: bool) -> Result<
         .map_err(|error| error.into())
         .and_then(|count| is_one(count, "Session"))
 }
+
+/// Get confirmed and enabled sessions of the user, last used first
+pub fn get_all_confirmed_with_user(
+    connector: &Connector,
+    user_id: &Uuid,
+) -> Result<Vec<Session>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    dsl::session
+        .select(SESSION_COLUMNS)
+        .filter(
+            dsl::user_id
+                .eq(user_id)
+                .and(dsl::confirmed.eq(true))
+                .and(dsl::disabled.eq(false)),
+        )
+        .order(dsl::last_used_at.desc().nulls_last())
+        .load::<Session>(&connection)
+        .map_err(|error| error.into())
+}
+
+/// Record the use of the session, at most once a minute
+pub fn set_used(connector: &Connector, id: &Uuid) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+    let now = Utc::now();
+
+    diesel::update(
+        dsl::session.filter(
+            dsl::id.eq(id).and(
+                dsl::last_used_at
+                    .is_null()
+                    .or(dsl::last_used_at.lt(now - Duration::minutes(1))),
+            ),
+        ),
+    )
+    .set(dsl::last_used_at.eq(now))
+    .execute(&connection)
+    .map(|_| ())
+    .map_err(|error| error.into())
+}
+
+/// Disable all sessions of the user except one, returning the number disabled
+pub fn set_all_disabled_with_user_except(
+    connector: &Connector,
+    user_id: &Uuid,
+    except_id: &Uuid,
+) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::update(
+        dsl::session.filter(
+            dsl::user_id
+                .eq(user_id)
+                .and(dsl::id.ne(except_id))
+                .and(dsl::disabled.eq(false)),
+        ),
+    )
+    .set(dsl::disabled.eq(true))
+    .execute(&connection)
+    .map_err(|error| error.into())
+}
+
+/// Disable sessions whose confirmation link, idle or absolute lifetime expired
+pub fn set_all_expired_disabled(connector: &Connector) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+    let now = Utc::now();
+    let configuration = &connector.configuration.session;
+
+    let confirmation_limit = now - Duration::minutes(configuration.confirmation_ttl);
+    let idle_limit = now - Duration::days(configuration.idle_ttl);
+    let absolute_limit = now - Duration::days(configuration.absolute_ttl);
+
+    diesel::update(
+        dsl::session.filter(
+            dsl::disabled.eq(false).and(
+                dsl::confirmed
+                    .eq(false)
+                    .and(dsl::created_at.le(confirmation_limit))
+                    .or(dsl::confirmed.eq(true).and(
+                        dsl::last_used_at
+                            .is_null()
+                            .or(dsl::last_used_at.le(idle_limit))
+                            .or(dsl::created_at.le(absolute_limit)),
+                    )),
+            ),
+        ),
+    )
+    .set(dsl::disabled.eq(true))
+    .execute(&connection)
+    .map_err(|error| error.into())
+}
@@ -0,0 +1,61 @@
+use chrono::NaiveDate;
+use diesel::sql_types::*;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug)]
+pub enum StatisticsGrouping {
+    Day,
+    Place,
+    Cell,
+}
+
+impl StatisticsGrouping {
+    /// Columns grouped by
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            StatisticsGrouping::Day => "day",
+            StatisticsGrouping::Place => "place_id, place_name",
+            StatisticsGrouping::Cell => "cell_longitude, cell_latitude",
+        }
+    }
+
+    /// Columns selected, NULL for the ones not grouped by
+    pub fn select_sql(&self) -> &'static str {
+        match self {
+            StatisticsGrouping::Day => {
+                "day, NULL::uuid AS place_id, NULL::text AS place_name, \
+                NULL::float8 AS cell_longitude, NULL::float8 AS cell_latitude"
+            }
+            StatisticsGrouping::Place => {
+                "NULL::date AS day, place_id, place_name, \
+                NULL::float8 AS cell_longitude, NULL::float8 AS cell_latitude"
+            }
+            StatisticsGrouping::Cell => {
+                "NULL::date AS day, NULL::uuid AS place_id, NULL::text AS place_name, \
+                cell_longitude, cell_latitude"
+            }
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+pub struct InfectionStatistics {
+    /// Local day of the place
+    #[sql_type = "Nullable<Date>"]
+    pub day: Option<NaiveDate>,
+    #[sql_type = "Nullable<diesel::sql_types::Uuid>"]
+    pub place_id: Option<Uuid>,
+    #[sql_type = "Nullable<Text>"]
+    pub place_name: Option<String>,
+    /// South-west corner of the grid cell
+    #[sql_type = "Nullable<Float8>"]
+    pub cell_longitude: Option<f64>,
+    #[sql_type = "Nullable<Float8>"]
+    pub cell_latitude: Option<f64>,
+    /// Approved infections
+    #[sql_type = "Int8"]
+    pub infections: i64,
+    /// Confirmed checkins flagged as potentially infected
+    #[sql_type = "Int8"]
+    pub exposures: i64,
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use super::error::Error;
+use crate::connector::Connector;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::*;
+
+pub use common::*;
+
+/// Aggregate approved infections and flagged checkins between two timestamps
+///
+/// Groups with fewer than `k_anonymity` exposures are suppressed, the number of
+/// suppressed groups is returned along with the others. Places without location
+/// are left out of the grid cells.
+pub fn get_all_aggregated(
+    connector: &Connector,
+    grouping: StatisticsGrouping,
+    region: Option<&String>,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+) -> Result<(Vec<InfectionStatistics>, usize), Error> {
+    let connection = connector.local.pool.get()?;
+
+    let statistics = diesel::sql_query(format!(
+        r#"
+        WITH event AS (
+            SELECT
+                infection.id AS infection_id,
+                NULL::uuid AS checkin_id,
+                place.id AS place_id,
+                place.name AS place_name,
+                place.location,
+                (infection.start_timestamp AT TIME ZONE place.timezone)::date AS day
+            FROM infection
+            INNER JOIN place ON place.id = ANY(infection.places_ids)
+            WHERE infection.status = 'approved'
+                AND infection.start_timestamp >= $1
+                AND infection.start_timestamp < $2
+                AND ($3::text IS NULL OR place.region = $3)
+            UNION ALL
+            SELECT
+                NULL::uuid AS infection_id,
+                checkin.id AS checkin_id,
+                place.id AS place_id,
+                place.name AS place_name,
+                place.location,
+                (checkin.start_timestamp AT TIME ZONE place.timezone)::date AS day
+            FROM checkin
+            INNER JOIN place ON place.id = checkin.place_id
+            WHERE checkin.confirmed = TRUE
+                AND checkin.potential_infection = TRUE
+                AND checkin.start_timestamp >= $1
+                AND checkin.start_timestamp < $2
+                AND ($3::text IS NULL OR place.region = $3)
+        ),
+        cell_event AS (
+            SELECT
+                event.*,
+                ST_X(ST_SnapToGrid(event.location, $4)) AS cell_longitude,
+                ST_Y(ST_SnapToGrid(event.location, $4)) AS cell_latitude
+            FROM event
+        )
+        SELECT
+            {select},
+            COUNT(DISTINCT infection_id) AS infections,
+            COUNT(checkin_id) AS exposures
+        FROM cell_event
+        GROUP BY {columns}
+        ORDER BY {columns}
+        "#,
+        select = grouping.select_sql(),
+        columns = grouping.as_sql()
+    ))
+    .bind::<Timestamptz, _>(from)
+    .bind::<Timestamptz, _>(to)
+    .bind::<Nullable<Text>, _>(region)
+    .bind::<Float8, _>(connector.configuration.statistics.grid_cell_size)
+    .load::<InfectionStatistics>(&connection)?;
+
+    let (statistics, suppressed): (Vec<InfectionStatistics>, Vec<InfectionStatistics>) = statistics
+        .into_iter()
+        .filter(|s| s.cell_longitude.is_some() || !matches!(grouping, StatisticsGrouping::Cell))
+        .partition(|s| s.exposures >= connector.configuration.statistics.k_anonymity);
+
+    Ok((statistics, suppressed.len()))
+}
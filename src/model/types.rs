@@ -37,6 +37,38 @@ pub fn join_gauge_levels(gauge_levels: Vec<GaugeLevel>) -> String {
         .join(",")
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, diesel_derive_enum::DbEnum)]
+#[DieselType = "Notification_kind"]
+#[allow(clippy::enum_variant_names)]
+pub enum NotificationKind {
+    InfectionWarning,
+    InfectionRetraction,
+    InfectionReport,
+    InfectionSummary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, diesel_derive_enum::DbEnum)]
+#[DieselType = "Notification_channel"]
+pub enum NotificationChannel {
+    Email,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, diesel_derive_enum::DbEnum)]
+#[DieselType = "Notification_status"]
+pub enum NotificationStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, diesel_derive_enum::DbEnum)]
+#[DieselType = "Infection_status"]
+pub enum InfectionStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
 #[derive(Debug, PartialEq, FromSqlRow, AsExpression)]
 #[sql_type = "Text"]
 pub struct Timezone {
@@ -1,5 +1,4 @@
 use super::super::schema::user;
-use chrono::{DateTime, Utc};
 use diesel::deserialize::{self, FromSql};
 use diesel::pg::Pg;
 use diesel::serialize::{self, IsNull, Output, ToSql};
@@ -13,6 +12,7 @@ use uuid::Uuid;
 pub enum UserRole {
     Public,
     Professional,
+    HealthAuthority,
 }
 
 #[derive(Queryable)]
@@ -22,11 +22,33 @@ pub struct User {
     pub email: String,
     pub role: UserRole,
     pub confirmed: bool,
-    pub disabled: bool,
-    pub updated_at: DateTime<Utc>,
-    pub created_at: DateTime<Utc>,
+    /// Region reviewed by a health authority, all regions if None
+    pub region: Option<String>,
+    /// Language of the emails
+    pub locale: String,
 }
 
+pub type UserColumns = (
+    user::id,
+    user::login,
+    user::email,
+    user::role,
+    user::confirmed,
+    user::region,
+    user::locale,
+);
+
+/// Columns read into `User`, in the order of its fields
+pub const USER_COLUMNS: UserColumns = (
+    user::id,
+    user::login,
+    user::email,
+    user::role,
+    user::confirmed,
+    user::region,
+    user::locale,
+);
+
 #[derive(Insertable)]
 #[table_name = "user"]
 pub struct UserInsert {
@@ -41,6 +63,7 @@ impl ToSql<Text, Pg> for UserRole {
         match *self {
             UserRole::Public => out.write_all(b"public")?,
             UserRole::Professional => out.write_all(b"professional")?,
+            UserRole::HealthAuthority => out.write_all(b"health_authority")?,
         }
         Ok(IsNull::No)
     }
@@ -51,6 +74,7 @@ impl FromSql<Text, Pg> for UserRole {
         match not_none!(bytes) {
             b"public" => Ok(UserRole::Public),
             b"professional" => Ok(UserRole::Professional),
+            b"health_authority" => Ok(UserRole::HealthAuthority),
             _ => Err("Unrecognized enum variant".into()),
         }
     }
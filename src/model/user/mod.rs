@@ -1,7 +1,7 @@
 mod common;
 
 use super::error::{is_one, Error};
-use super::organization::Organization;
+use super::organization::{Organization, ORGANIZATION_COLUMNS};
 use super::schema::{organization, user::dsl};
 use crate::connector::Connector;
 use diesel::prelude::*;
@@ -13,6 +13,7 @@ pub fn get(connector: &Connector, id: &Uuid) -> Result<User, Error> {
     let connection = connector.local.pool.get()?;
 
     dsl::user
+        .select(USER_COLUMNS)
         .filter(dsl::id.eq(id).and(dsl::disabled.eq(false)))
         .first::<User>(&connection)
         .map_err(|error| error.into())
@@ -33,6 +34,7 @@ pub fn get_with_login(connector: &Connector, login: &String) -> Result<User, Err
     let connection = connector.local.pool.get()?;
 
     dsl::user
+        .select(USER_COLUMNS)
         .filter(dsl::login.eq(login).and(dsl::disabled.eq(false)))
         .first::<User>(&connection)
         .map_err(|error| error.into())
@@ -46,6 +48,7 @@ pub fn get_with_organization(
 
     dsl::user
         .left_join(organization::dsl::organization)
+        .select((USER_COLUMNS, ORGANIZATION_COLUMNS.nullable()))
         .filter(dsl::id.eq(id).and(dsl::disabled.eq(false)))
         .first::<(User, Option<Organization>)>(&connection)
         .map_err(|error| error.into())
@@ -78,6 +81,26 @@ pub fn update_role(connector: &Connector, id: Uuid, role: UserRole) -> Result<()
         .and_then(|count| is_one(count, "User"))
 }
 
+pub fn set_region(connector: &Connector, id: Uuid, region: Option<String>) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::update(dsl::user.filter(dsl::id.eq(id).and(dsl::disabled.eq(false))))
+        .set(dsl::region.eq(region))
+        .execute(&connection)
+        .map_err(|error| error.into())
+        .and_then(|count| is_one(count, "User"))
+}
+
+pub fn set_locale(connector: &Connector, id: &Uuid, locale: &str) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::update(dsl::user.filter(dsl::id.eq(id).and(dsl::disabled.eq(false))))
+        .set(dsl::locale.eq(locale))
+        .execute(&connection)
+        .map_err(|error| error.into())
+        .and_then(|count| is_one(count, "User"))
+}
+
 pub fn confirm(connector: &Connector, id: &Uuid) -> Result<(), Error> {
     let connection = connector.local.pool.get()?;
 
@@ -102,6 +125,47 @@ pub fn set_email_with_login(
         .and_then(|count| is_one(count, "User"))
 }
 
+pub fn get_all(connector: &Connector) -> Result<Vec<User>, Error> {
+    let connection = connector.local.pool.get()?;
+
+    dsl::user
+        .select(USER_COLUMNS)
+        .load::<User>(&connection)
+        .map_err(|error| error.into())
+}
+
+pub fn set_login(connector: &Connector, id: &Uuid, login: &String) -> Result<(), Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::update(dsl::user.find(id))
+        .set(dsl::login.eq(login))
+        .execute(&connection)
+        .map_err(|error| error.into())
+        .and_then(|count| is_one(count, "User"))
+}
+
+/// Replace a login derived before the pepper, unless the user already signed up again
+pub fn upgrade_legacy_login(
+    connector: &Connector,
+    legacy_login: &str,
+    login: &str,
+) -> Result<usize, Error> {
+    let connection = connector.local.pool.get()?;
+
+    diesel::sql_query(
+        r#"
+        UPDATE "user"
+        SET login = $2
+        WHERE login = $1
+            AND NOT EXISTS (SELECT 1 FROM "user" AS upgraded WHERE upgraded.login = $2)
+        "#,
+    )
+    .bind::<diesel::sql_types::Text, _>(legacy_login)
+    .bind::<diesel::sql_types::Text, _>(login)
+    .execute(&connection)
+    .map_err(|error| error.into())
+}
+
 pub fn delete(connector: &Connector, id: &Uuid) -> Result<(), Error> {
     let connection = connector.local.pool.get()?;
 
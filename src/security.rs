@@ -1,15 +1,151 @@
+use chrono::{DateTime, Utc};
+use openssl::hash::MessageDigest;
+use openssl::memcmp;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 use sha3::{Digest, Sha3_512};
+use uuid::Uuid;
 
-pub fn hash(value: String) -> String {
-    let hash = Sha3_512::new()
-        .chain(value.into_bytes().as_slice())
-        .finalize();
-    hex::encode(hash)
+/// Prefix of token hashes keyed by the pepper, older ones being bare SHA3-512
+const TOKEN_HASH_SCHEME: &str = "hmac-sha512";
+
+/// Claims of a signed access token
+#[derive(Serialize, Deserialize)]
+struct AccessTokenClaims {
+    /// Session identifier
+    sid: Uuid,
+    /// Expiration timestamp
+    exp: i64,
+}
+
+pub enum TokenVerification {
+    Invalid,
+    Valid,
+    /// Valid against a bare SHA3-512 hash, which should be upgraded
+    ValidLegacy,
+}
+
+/// Hash a token to store, keyed by the pepper
+///
+/// Tokens being 512-bit random values, a slow KDF would only cost CPU on every request.
+pub fn hash_token(pepper: &str, token: &str) -> String {
+    format!(
+        "{}${}",
+        TOKEN_HASH_SCHEME,
+        hex::encode(sign_hmac(pepper, token.as_bytes(), MessageDigest::sha512()))
+    )
+}
+
+/// Check a token against its stored hash, in constant time
+pub fn verify_token(pepper: &str, token: &str, hashed_token: &str) -> TokenVerification {
+    let (expected, computed, verification) = match hashed_token.split_once('$') {
+        Some((TOKEN_HASH_SCHEME, hash)) => (
+            hash,
+            hex::encode(sign_hmac(pepper, token.as_bytes(), MessageDigest::sha512())),
+            TokenVerification::Valid,
+        ),
+        Some(_) => return TokenVerification::Invalid,
+        None => (
+            hashed_token,
+            legacy_hash_token(token),
+            TokenVerification::ValidLegacy,
+        ),
+    };
+
+    if expected.len() == computed.len() && memcmp::eq(expected.as_bytes(), computed.as_bytes()) {
+        verification
+    } else {
+        TokenVerification::Invalid
+    }
 }
 
 pub fn generate_token() -> String {
     let random_bytes: Vec<u8> = (0..64).map(|_| random::<u8>()).collect();
-    let hash = Sha3_512::new().chain(&random_bytes.as_slice()).finalize();
+    let hash = Sha3_512::new().chain(random_bytes.as_slice()).finalize();
+    hex::encode(hash)
+}
+
+/// Derive the login of an email with a server-side pepper, so that it cannot be
+/// reversed from a list of emails without the secret
+pub fn get_auth_from_email(login_pepper: &str, email: String) -> (String, String) {
+    let cleaned_email = email.to_lowercase();
+
+    let login = hex::encode(sign_hmac(
+        login_pepper,
+        cleaned_email.as_bytes(),
+        MessageDigest::sha3_512(),
+    ));
+
+    (login, cleaned_email)
+}
+
+/// Login of an email as derived before the pepper, a bare SHA3-512
+pub fn get_legacy_login(cleaned_email: &str) -> String {
+    legacy_hash_token(cleaned_email)
+}
+
+/// Sign a short-lived access token of the session, a JWT signed with HS512
+pub fn sign_access_token(secret: &str, session_id: Uuid, expires_at: DateTime<Utc>) -> String {
+    let header = serde_json::json!({ "typ": "JWT", "alg": "HS512" });
+    let claims = AccessTokenClaims {
+        sid: session_id,
+        exp: expires_at.timestamp(),
+    };
+
+    let unsigned_token = format!(
+        "{}.{}",
+        base64::encode_config(header.to_string(), base64::URL_SAFE_NO_PAD),
+        base64::encode_config(
+            serde_json::to_vec(&claims).expect("Unable to serialize access token"),
+            base64::URL_SAFE_NO_PAD
+        )
+    );
+
+    let signature = sign_hmac(secret, unsigned_token.as_bytes(), MessageDigest::sha512());
+
+    format!(
+        "{}.{}",
+        unsigned_token,
+        base64::encode_config(signature, base64::URL_SAFE_NO_PAD)
+    )
+}
+
+/// Get the session of an access token if its signature is valid and it has not expired
+pub fn verify_access_token(secret: &str, token: &str) -> Option<Uuid> {
+    let (unsigned_token, signature) = token.rsplit_once('.')?;
+    let (_, claims) = unsigned_token.split_once('.')?;
+
+    let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD).ok()?;
+    let expected = sign_hmac(secret, unsigned_token.as_bytes(), MessageDigest::sha512());
+
+    if signature.len() != expected.len() || !memcmp::eq(&signature, &expected) {
+        return None;
+    }
+
+    let claims: AccessTokenClaims = base64::decode_config(claims, base64::URL_SAFE_NO_PAD)
+        .ok()
+        .and_then(|claims| serde_json::from_slice(&claims).ok())?;
+
+    if claims.exp <= Utc::now().timestamp() {
+        return None;
+    }
+
+    Some(claims.sid)
+}
+
+fn sign_hmac(secret: &str, data: &[u8], digest: MessageDigest) -> Vec<u8> {
+    PKey::hmac(secret.as_bytes())
+        .and_then(|key| {
+            let mut signer = Signer::new(digest, &key)?;
+            signer.update(data)?;
+            signer.sign_to_vec()
+        })
+        .expect("Unable to sign with HMAC")
+}
+
+fn legacy_hash_token(token: &str) -> String {
+    let hash = Sha3_512::new().chain(token.as_bytes()).finalize();
     hex::encode(hash)
 }
@@ -1,57 +1,82 @@
 use super::error::Error;
-use super::types::{Context, ProfessionalUser, PublicUser};
+use super::types::{Context, HealthAuthorityUser, ProfessionalUser, PublicUser};
 use crate::connector::Connector;
 use crate::model::session::Session;
 use crate::model::{session, user};
-use crate::security::hash;
+use crate::security::{hash_token, verify_access_token, verify_token, TokenVerification};
 use base64::decode;
 use std::str::FromStr;
 use tracing::debug;
 use uuid::Uuid;
 use warp::{reject, Filter, Rejection};
 
-const SCHEME: &str = "Basic";
+const BASIC_SCHEME: &str = "Basic";
+const BEARER_SCHEME: &str = "Bearer";
 
 struct Credentials {
     pub username: String,
     pub password: String,
 }
 
+enum Authorization {
+    /// Session identifier and token, as Basic or opaque Bearer `<session_id>.<token>`
+    Credentials(Credentials),
+    /// Signed short-lived token, as Bearer
+    AccessToken(String),
+}
+
 pub fn public_user_filter(
     context: Context,
 ) -> impl Filter<Extract = (PublicUser,), Error = Rejection> + Clone {
-    auth_filter(context, |connector, session| {
-        user::get(&connector, &session.user_id)
+    auth_filter(context, true, public_user)
+}
+
+/// Public user authenticated with session credentials only, refusing access tokens
+pub fn public_user_credentials_filter(
+    context: Context,
+) -> impl Filter<Extract = (PublicUser,), Error = Rejection> + Clone {
+    auth_filter(context, false, public_user)
+}
+
+fn public_user(connector: &Connector, session: Session) -> Option<PublicUser> {
+    user::get(connector, &session.user_id)
+        .ok()
+        .map(|user| PublicUser {
+            session: session.into(),
+            user: user.into(),
+        })
+}
+
+pub fn professional_user_filter(
+    context: Context,
+) -> impl Filter<Extract = (ProfessionalUser,), Error = Rejection> + Clone {
+    auth_filter(context, true, |connector, session| {
+        user::get_with_organization(connector, &session.user_id)
             .ok()
-            .map(|user| PublicUser {
-                session: session.into(),
-                user: user.into(),
+            .and_then(|(_, organisation)| organisation)
+            .map(|org| ProfessionalUser {
+                organization: org.into(),
             })
     })
 }
 
-pub fn professional_user_filter(
+pub fn health_authority_user_filter(
     context: Context,
-) -> impl Filter<Extract = (ProfessionalUser,), Error = Rejection> + Clone {
-    auth_filter(context, |connector, session| {
-        user::get_with_organization(&connector, &session.user_id)
+) -> impl Filter<Extract = (HealthAuthorityUser,), Error = Rejection> + Clone {
+    auth_filter(context, true, |connector, session| {
+        user::get(connector, &session.user_id)
             .ok()
-            .and_then(|(user, organisation)| {
-                if let Some(org) = organisation {
-                    Some(ProfessionalUser {
-                        session: session.into(),
-                        user: user.into(),
-                        organization: org.into(),
-                    })
-                } else {
-                    None
-                }
+            .filter(|user| user.role == user::UserRole::HealthAuthority)
+            .map(|user| HealthAuthorityUser {
+                region: user.region.clone(),
+                user: user.into(),
             })
     })
 }
 
 fn auth_filter<T, F>(
     context: Context,
+    allow_access_token: bool,
     get_user: F,
 ) -> impl Filter<Extract = (T,), Error = Rejection> + Clone
 where
@@ -60,12 +85,26 @@ where
     warp::header::<String>("authorization")
         .map(move |header| (header, context.clone(), get_user.clone()))
         .and_then(
-            |(header, context, get_user): (String, Context, F)| async move {
+            move |(header, context, get_user): (String, Context, F)| async move {
                 // Prepare connector
                 let connector = context.builder.create();
 
-                let user = decrypt_basic_header(header)
-                    .and_then(|credentials| credentials_to_session(&connector, credentials))
+                let session = match parse_authorization_header(header) {
+                    Some(Authorization::Credentials(credentials)) => {
+                        credentials_to_session(&connector, credentials)
+                    }
+                    Some(Authorization::AccessToken(token)) if allow_access_token => {
+                        access_token_to_session(&connector, &token)
+                    }
+                    _ => None,
+                };
+
+                let user = session
+                    .and_then(|session| {
+                        session::set_used(&connector, &session.id)
+                            .ok()
+                            .map(|_| session)
+                    })
                     .and_then(|session| get_user(&connector, session));
 
                 match user {
@@ -76,24 +115,68 @@ where
         )
 }
 
-fn decrypt_basic_header(header: String) -> Option<Credentials> {
-    if !header.starts_with(SCHEME) || header.len() <= SCHEME.len() + 1 {
-        return None;
-    }
+fn parse_authorization_header(header: String) -> Option<Authorization> {
+    let (scheme, value) = header.split_once(' ')?;
+    let value = value.trim();
 
-    match header[SCHEME.len() + 1..].parse::<Credentials>() {
-        Ok(h) => Some(h),
-        Err(_) => None,
+    if scheme.eq_ignore_ascii_case(BASIC_SCHEME) {
+        value
+            .parse::<Credentials>()
+            .ok()
+            .map(Authorization::Credentials)
+    } else if scheme.eq_ignore_ascii_case(BEARER_SCHEME) {
+        // Opaque tokens have two parts, signed ones three
+        match value.split('.').collect::<Vec<&str>>().as_slice() {
+            [username, password] => Some(Authorization::Credentials(Credentials {
+                username: String::from(*username),
+                password: String::from(*password),
+            })),
+            [_, _, _] => Some(Authorization::AccessToken(String::from(value))),
+            _ => None,
+        }
+    } else {
+        None
     }
 }
 
 fn credentials_to_session(connector: &Connector, credentials: Credentials) -> Option<Session> {
-    Uuid::parse_str(&credentials.username)
+    let session = Uuid::parse_str(&credentials.username)
         .ok()
-        .map(|session_id| (session_id, hash(credentials.password)))
-        .and_then(|(sid, ht)| session::get_confirmed(&connector, &sid, &ht).ok())
+        .and_then(|session_id| session::get_confirmed(connector, &session_id).ok())
+        .flatten()?;
+
+    let pepper = &connector.configuration.security.login_pepper;
+
+    match verify_token(
+        pepper,
+        &credentials.password,
+        session.hashed_token.as_deref()?,
+    ) {
+        TokenVerification::Valid => Some(session),
+        TokenVerification::ValidLegacy => {
+            // Upgrade the legacy hash now that the token is known
+            session::set_hashed_token(
+                connector,
+                &session.id,
+                &hash_token(pepper, &credentials.password),
+            )
+            .ok()
+            .map(|_| session)
+        }
+        TokenVerification::Invalid => None,
+    }
+}
+
+fn access_token_to_session(connector: &Connector, token: &str) -> Option<Session> {
+    let secret = connector
+        .configuration
+        .security
+        .access_token_secret
+        .as_ref()?;
+
+    verify_access_token(secret, token)
+        .and_then(|session_id| session::get_confirmed(connector, &session_id).ok())
         .flatten()
-        .map(|s| s.into())
 }
 
 struct CredentialsError;
@@ -1,9 +1,15 @@
-use super::super::authorization::public_user_filter;
+use super::super::authorization::{public_user_credentials_filter, public_user_filter};
 use super::super::error::Error;
-use super::super::session::{create_session, get_auth_from_email};
+use super::super::rate_limit::{check_login_rate_limit, ip_rate_limit_filter};
+use super::super::session::{create_session, update_locale};
 use super::super::types::*;
-use crate::model::{checkin, organization, session, user};
-use crate::security::{generate_token, hash};
+use crate::model::error::Error as ModelError;
+use crate::model::{checkin, organization, push_subscription, session, user};
+use crate::security::{
+    generate_token, get_auth_from_email, get_legacy_login, hash_token, sign_access_token,
+    verify_token, TokenVerification,
+};
+use chrono::{Duration, Utc};
 use uuid::Uuid;
 use validator::Validate;
 use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
@@ -15,9 +21,11 @@ pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
     // POST /login {email, role, organization_name?} -> 200
     let login = warp::post()
         .and(warp::path!("login"))
+        .and(ip_rate_limit_filter(context.clone(), "login"))
         .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
         .and(warp::body::json())
         .and(warp::header::<String>("user-agent"))
+        .and(warp::header::optional::<String>("accept-language"))
         .and(context_filter.clone())
         .and_then(login);
 
@@ -31,12 +39,48 @@ pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
     // POST /session/<session_id>/validate {confirmation_token} -> Credentials
     let session_validate = warp::post()
         .and(warp::path!("session" / Uuid / "validate"))
+        .and(ip_rate_limit_filter(context.clone(), "validate"))
         .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
         .and(warp::body::json())
         .and(context_filter.clone())
         .and_then(validate);
 
-    login.or(logout).or(session_validate).boxed()
+    // POST /session/refresh -> AccessToken
+    let session_refresh = warp::post()
+        .and(warp::path!("session" / "refresh"))
+        .and(public_user_credentials_filter(context.clone()))
+        .and(context_filter.clone())
+        .and_then(refresh);
+
+    // POST /logout/others -> 200
+    let logout_others = warp::post()
+        .and(warp::path!("logout" / "others"))
+        .and(public_user_filter(context.clone()))
+        .and(context_filter.clone())
+        .and_then(logout_others);
+
+    // GET /sessions -> Vec<SessionDescription>
+    let get_sessions = warp::get()
+        .and(warp::path!("sessions"))
+        .and(public_user_filter(context.clone()))
+        .and(context_filter.clone())
+        .and_then(get_sessions);
+
+    // DELETE /session/<session_id> -> 200
+    let delete_session = warp::delete()
+        .and(warp::path!("session" / Uuid))
+        .and(public_user_filter(context.clone()))
+        .and(context_filter.clone())
+        .and_then(delete_session);
+
+    login
+        .or(logout)
+        .or(logout_others)
+        .or(session_validate)
+        .or(session_refresh)
+        .or(get_sessions)
+        .or(delete_session)
+        .boxed()
 }
 
 async fn validate(
@@ -54,17 +98,24 @@ async fn validate(
     // Prepare connector
     let connector = context.builder.create();
 
-    // Hash token
-    let hashed_confirmation_token = hash(data.confirmation_token);
-
     // Find session
     // TODO: Handle error code with already confirmed session
-    let (session, user) =
-        session::get_unconfirmed(&connector, &session_id, &hashed_confirmation_token)?;
+    let (session, user) = session::get_unconfirmed(&connector, &session_id)?;
+
+    // Check token
+    let hashed_confirmation_token = session.hashed_confirmation_token.unwrap_or_default();
+
+    let pepper = &connector.configuration.security.login_pepper;
+
+    if let TokenVerification::Invalid =
+        verify_token(pepper, &data.confirmation_token, &hashed_confirmation_token)
+    {
+        return Err(ModelError::NotFound.into());
+    }
 
     // Generate token and save
     let token = generate_token();
-    let hashed_token = hash(token.clone());
+    let hashed_token = hash_token(pepper, &token);
 
     session::confirm(&connector, &session.id, &hashed_token)?;
     user::confirm(&connector, &session.user_id)?;
@@ -78,13 +129,30 @@ async fn validate(
     }))
 }
 
+async fn refresh(public: PublicUser, context: Context) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+    let configuration = &connector.configuration.security;
+
+    // Access tokens are disabled without secret
+    let secret = match &configuration.access_token_secret {
+        Some(secret) => secret,
+        None => return Err(warp::reject::not_found()),
+    };
+
+    let expires_at = Utc::now() + Duration::seconds(configuration.access_token_ttl);
+
+    Ok(warp::reply::json(&AccessToken {
+        access_token: sign_access_token(secret, public.session.id, expires_at),
+        expires_at,
+    }))
+}
+
 async fn login(
     data: LoginForm,
     user_agent: String,
+    accept_language: Option<String>,
     context: Context,
 ) -> Result<impl Reply, Rejection> {
-    // TODO: Rate limit if more than 3 unconfirmed in the last 4 minutes
-
     // Validate data
     if let Err(errors) = data.validate() {
         return Err(warp::reject::custom(Error::InvalidDataWithDetails {
@@ -96,13 +164,30 @@ async fn login(
     let connector = context.builder.create();
 
     // Get login
-    let (login, cleaned_email) = get_auth_from_email(data.email.clone());
+    let (login, cleaned_email) = get_auth_from_email(
+        &connector.configuration.security.login_pepper,
+        data.email.clone(),
+    );
+
+    // Rewrite a login derived before the pepper, otherwise the user would sign up again
+    user::upgrade_legacy_login(&connector, &get_legacy_login(&cleaned_email), &login)?;
+
+    // Limit confirmation emails sent to the same address
+    check_login_rate_limit(&connector, &login)?;
 
     if !data.fallback_on_sign_up && !user::exist_with_login(&connector, &login)? {
         // Do not create user if it doesn't exist
         return Err(warp::reject::custom(Error::Unauthorized));
     }
 
+    if data.role == user::UserRole::HealthAuthority {
+        // Health authorities are only granted through the CLI
+        match user::get_with_login(&connector, &login) {
+            Ok(user) if user.role == user::UserRole::HealthAuthority => (),
+            _ => return Err(warp::reject::custom(Error::Unauthorized)),
+        }
+    }
+
     // Upsert user
     let user = user::insert(
         &connector,
@@ -127,7 +212,7 @@ async fn login(
                     },
                 )?;
 
-                if user.role != data.role {
+                if user.role == user::UserRole::Public {
                     // Upgrade user to pro user
                     user::update_role(&connector, user.id, data.role)?;
                 }
@@ -136,21 +221,84 @@ async fn login(
         }
     }
 
+    let locale = update_locale(&connector, &user.id, &user.locale, accept_language)?;
+
     let redirect_page = match user.role {
         user::UserRole::Public => RedirectPage::Checkins,
         user::UserRole::Professional => RedirectPage::Places,
+        user::UserRole::HealthAuthority => RedirectPage::Infections,
     };
 
     // Create session with confirmation token
-    let session =
-        create_session(&connector, user.id, data.email, user_agent, redirect_page).await?;
+    let session = create_session(
+        &connector,
+        user.id,
+        data.email,
+        user_agent,
+        redirect_page,
+        locale,
+    )
+    .await?;
 
     // Return session_id
     Ok(warp::reply::json(&session))
 }
 
 async fn logout(public: PublicUser, context: Context) -> Result<impl Reply, Rejection> {
-    session::set_disabled(&context.builder.create(), &public.session.id, true)?;
+    let connector = context.builder.create();
+
+    session::set_disabled(&connector, &public.session.id, true)?;
+    push_subscription::delete_with_session(&connector, &public.session.id)?;
+
+    Ok(warp::reply())
+}
+
+async fn logout_others(public: PublicUser, context: Context) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    session::set_all_disabled_with_user_except(&connector, &public.user.id, &public.session.id)?;
+    push_subscription::delete_all_with_user_except_session(
+        &connector,
+        &public.user.id,
+        &public.session.id,
+    )?;
+
+    Ok(warp::reply())
+}
+
+async fn get_sessions(public: PublicUser, context: Context) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    let sessions: Vec<SessionDescription> =
+        session::get_all_confirmed_with_user(&connector, &public.user.id)?
+            .into_iter()
+            .map(|s| SessionDescription {
+                current: s.id == public.session.id,
+                id: s.id,
+                description: s.description,
+                created_at: s.created_at,
+                last_used_at: s.last_used_at,
+            })
+            .collect();
+
+    Ok(warp::reply::json(&sessions))
+}
+
+async fn delete_session(
+    session_id: Uuid,
+    public: PublicUser,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    let sessions = session::get_all_confirmed_with_user(&connector, &public.user.id)?;
+
+    if !sessions.iter().any(|s| s.id == session_id) {
+        return Err(warp::reject::not_found());
+    }
+
+    session::set_disabled(&connector, &session_id, true)?;
+    push_subscription::delete_with_session(&connector, &session_id)?;
 
     Ok(warp::reply())
 }
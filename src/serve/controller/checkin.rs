@@ -1,8 +1,12 @@
 use super::super::authorization::public_user_filter;
 use super::super::error::Error;
-use super::super::session::{create_session, get_auth_from_email};
+use super::super::rate_limit::{check_login_rate_limit, ip_rate_limit_filter};
+use super::super::session::{create_session, update_locale};
 use super::super::types::*;
-use crate::model::{checkin, place, user};
+use crate::model::error::Error as ModelError;
+use crate::model::opening_hour::OpeningStatus;
+use crate::model::{checkin, opening_hour, place, user};
+use crate::security::{get_auth_from_email, get_legacy_login};
 use chrono::{Duration, Utc};
 use uuid::Uuid;
 use validator::Validate;
@@ -15,12 +19,14 @@ pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
     // POST /checkin {uuid, email, duration} -> 200
     let checkin = warp::post()
         .and(warp::path!("checkin"))
+        .and(ip_rate_limit_filter(context.clone(), "checkin"))
         .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
         .and(warp::body::json())
         .and(warp::header::<String>("user-agent"))
+        .and(warp::header::optional::<String>("accept-language"))
         .and(
             public_user_filter(context.clone())
-                .map(|u| Some(u))
+                .map(Some)
                 .or(warp::any().map(|| None))
                 .unify(),
         )
@@ -58,6 +64,7 @@ pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
 async fn create(
     data: CheckinForm,
     user_agent: String,
+    accept_language: Option<String>,
     public: Option<PublicUser>,
     context: Context,
 ) -> Result<impl Reply, Rejection> {
@@ -81,16 +88,47 @@ async fn create(
         }
     }
 
+    // Check if place is open and leave before evacuation
+    let start_timestamp = Utc::now();
+    let mut end_timestamp = start_timestamp + Duration::minutes(data.duration);
+
+    match opening_hour::get_status(&connector, &place.id, &start_timestamp)? {
+        OpeningStatus::Unscheduled => (),
+        OpeningStatus::Open {
+            evacuation_timestamp,
+        } => {
+            if evacuation_timestamp < end_timestamp {
+                end_timestamp = evacuation_timestamp;
+            }
+        }
+        OpeningStatus::Closed => return Err(warp::reject::custom(Error::PlaceClosed)),
+    }
+
     // Hash email to get login
-    let (login, cleaned_email) = get_auth_from_email(data.email.clone());
+    let (login, cleaned_email) = get_auth_from_email(
+        &connector.configuration.security.login_pepper,
+        data.email.clone(),
+    );
+
+    // Rewrite a login derived before the pepper, otherwise the user would sign up again
+    user::upgrade_legacy_login(&connector, &get_legacy_login(&cleaned_email), &login)?;
 
     // Generate user and session
     let (user, session) = match public {
         Some(public) => {
             user::set_email_with_login(&connector, &login, &cleaned_email)?;
+            update_locale(
+                &connector,
+                &public.user.id,
+                &public.user.locale,
+                accept_language,
+            )?;
             (public.user, public.session)
         }
         None => {
+            // Limit confirmation emails sent to the same address
+            check_login_rate_limit(&connector, &login)?;
+
             let user: User = user::insert(
                 &connector,
                 &user::UserInsert {
@@ -102,6 +140,8 @@ async fn create(
             )?
             .into();
 
+            let locale = update_locale(&connector, &user.id, &user.locale, accept_language)?;
+
             let session = create_session(
                 &connector,
                 user.id,
@@ -110,6 +150,7 @@ async fn create(
                 RedirectPage::CheckinConfirmation {
                     place_id: data.place_id,
                 },
+                locale,
             )
             .await?;
 
@@ -117,20 +158,24 @@ async fn create(
         }
     };
 
-    // Create checkin
+    // Create checkin, the gauge being checked again while locked
     checkin::insert(
         &connector,
         &checkin::CheckinInsert {
             place_id: data.place_id,
             session_id: session.id,
             user_id: user.id,
-            start_timestamp: Utc::now(),
-            end_timestamp: Utc::now() + Duration::minutes(data.duration.into()),
-            duration: data.duration,
+            start_timestamp,
+            end_timestamp,
+            duration: (end_timestamp - start_timestamp).num_minutes(),
             confirmed: session.confirmed,
             number: data.number,
         },
-    )?;
+    )
+    .map_err(|error| match error {
+        ModelError::MaximumGaugeReached => warp::reject::custom(Error::MaximumGaugeReached),
+        error => error.into(),
+    })?;
 
     // Return session_id
     Ok(warp::reply::json(&session))
@@ -1,9 +1,11 @@
-use super::super::authorization::professional_user_filter;
+use super::super::authorization::{health_authority_user_filter, professional_user_filter};
 use super::super::error::Error;
+use super::super::query::query_qs;
 use super::super::types::*;
-use crate::connector::email::template::InfectionWarningEmail;
-use crate::model::{checkin, infection, place};
+use crate::connector::Connector;
+use crate::model::{infection, notification, place};
 use chrono::{Duration, Utc};
+use uuid::Uuid;
 use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
 
 pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
@@ -26,7 +28,68 @@ pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
         .and(context_filter.clone())
         .and_then(get_all);
 
-    create_infection.or(get_infections).boxed()
+    // GET /infection/<id> -> InfectionSummary
+    let get_infection = warp::get()
+        .and(warp::path!("infection" / Uuid))
+        .and(professional_user_filter(context.clone()))
+        .and(context_filter.clone())
+        .and_then(get);
+
+    // PUT /infection/<id>?notify=true -> Infection
+    let update_infection = warp::put()
+        .and(warp::path!("infection" / Uuid))
+        .and(professional_user_filter(context.clone()))
+        .and(query_qs())
+        .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
+        .and(warp::body::json())
+        .and(context_filter.clone())
+        .and_then(update);
+
+    // DELETE /infection/<id>?notify=true -> 200
+    let delete_infection = warp::delete()
+        .and(warp::path!("infection" / Uuid))
+        .and(professional_user_filter(context.clone()))
+        .and(query_qs())
+        .and(context_filter.clone())
+        .and_then(delete);
+
+    // GET /infections/review?status=pending -> Vec<Infection>
+    let get_infections_review = warp::get()
+        .and(warp::path!("infections" / "review"))
+        .and(health_authority_user_filter(context.clone()))
+        .and(query_qs())
+        .and(context_filter.clone())
+        .and_then(get_all_review);
+
+    // POST /infection/<id>/approve -> Infection
+    let approve_infection = warp::post()
+        .and(warp::path!("infection" / Uuid / "approve"))
+        .and(health_authority_user_filter(context.clone()))
+        .and(context_filter.clone())
+        .and_then(approve);
+
+    // POST /infection/<id>/reject -> Infection, retracting it if approved
+    let reject_infection = warp::post()
+        .and(warp::path!("infection" / Uuid / "reject"))
+        .and(health_authority_user_filter(context.clone()))
+        .and(context_filter.clone())
+        .and_then(reject);
+
+    create_infection
+        .or(get_infections)
+        .or(get_infection)
+        .or(update_infection)
+        .or(delete_infection)
+        .or(get_infections_review)
+        .or(approve_infection)
+        .or(reject_infection)
+        .boxed()
+}
+
+fn is_valid_period(data: &InfectionForm) -> bool {
+    data.start_timestamp < data.end_timestamp
+        && data.end_timestamp <= Utc::now()
+        && data.end_timestamp - data.start_timestamp < Duration::minutes(720)
 }
 
 async fn create(
@@ -35,10 +98,7 @@ async fn create(
     context: Context,
 ) -> Result<impl Reply, Rejection> {
     // Validate dates and places
-    if data.start_timestamp >= data.end_timestamp
-        || data.end_timestamp > Utc::now()
-        || data.end_timestamp - data.start_timestamp >= Duration::minutes(720)
-    {
+    if !is_valid_period(&data) {
         return Err(warp::reject::custom(Error::InvalidData));
     }
 
@@ -46,7 +106,7 @@ async fn create(
 
     place::validate_places_owned(&connector, &professional.organization.id, &data.places_ids)?;
 
-    // Insert infection
+    // Insert infection, pending for review by a health authority
     let infection_id = infection::insert(
         &connector,
         &infection::InfectionInsert {
@@ -54,49 +114,225 @@ async fn create(
             places_ids: data.places_ids.clone(),
             start_timestamp: data.start_timestamp,
             end_timestamp: data.end_timestamp,
+            reported_by: None,
         },
     )?;
 
-    // Notify infected
-    let infected_users = checkin::get_potential_infections(
+    let new_infection = get_with_notifications(&connector, &infection_id)?;
+
+    Ok(warp::reply::json(&new_infection))
+}
+
+async fn get_all(
+    professional: ProfessionalUser,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    let infections =
+        infection::get_all_with_organization(&connector, &professional.organization.id)?;
+
+    let infections_ids: Vec<Uuid> = infections.iter().map(|(i, _)| i.id).collect();
+    let mut stats = notification::get_stats_with_infections(&connector, &infections_ids)?;
+
+    let infections: Vec<Infection> = infections
+        .into_iter()
+        .map(|(i, o)| {
+            let notifications = stats.remove(&i.id).unwrap_or_default();
+            (i, o, notifications).into()
+        })
+        .collect();
+
+    Ok(warp::reply::json(&infections))
+}
+
+async fn get(
+    infection_id: Uuid,
+    professional: ProfessionalUser,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    let (_, organization) = infection::get_with_organization(&connector, &infection_id)?;
+
+    if organization.id != professional.organization.id {
+        return Err(warp::reject::not_found());
+    }
+
+    let places = notification::get_summary_with_infection(&connector, &infection_id)?
+        .into_iter()
+        .map(|p| p.into())
+        .collect();
+
+    Ok(warp::reply::json(&InfectionSummary {
+        infection: get_with_notifications(&connector, &infection_id)?,
+        places,
+    }))
+}
+
+async fn update(
+    infection_id: Uuid,
+    professional: ProfessionalUser,
+    query: InfectionRetractionQuery,
+    data: InfectionForm,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    // Validate dates and places
+    if !is_valid_period(&data) {
+        return Err(warp::reject::custom(Error::InvalidData));
+    }
+
+    let connector = context.builder.create();
+
+    let (infection, organization) = infection::get_with_organization(&connector, &infection_id)?;
+
+    if organization.id != professional.organization.id {
+        return Err(warp::reject::not_found());
+    }
+
+    if infection.reported_by.is_some() {
+        return Err(warp::reject::custom(Error::SelfReportedInfection));
+    }
+
+    place::validate_places_owned(&connector, &professional.organization.id, &data.places_ids)?;
+
+    // Update infection, submitted again for review
+    infection::update(
         &connector,
-        &data.places_ids,
-        &data.start_timestamp,
-        &data.end_timestamp,
+        &infection_id,
+        &infection::InfectionUpdate {
+            places_ids: data.places_ids.clone(),
+            start_timestamp: data.start_timestamp,
+            end_timestamp: data.end_timestamp,
+        },
+        query.notify.unwrap_or(false),
     )?;
 
-    connector
-        .email
-        .send(
-            infected_users
-                .iter()
-                .map(|(checkin, user, place)| InfectionWarningEmail {
-                    to: user.email.clone(),
-                    organization_name: professional.organization.name.clone(),
-                    place_name: place.name.clone(),
-                    checkin_datetime: checkin.start_timestamp,
-                })
-                .collect(),
-        )
-        .await;
-
-    let new_infection: Infection =
-        infection::get_with_organization(&connector, &infection_id)?.into();
+    let updated_infection = get_with_notifications(&connector, &infection_id)?;
 
-    Ok(warp::reply::json(&new_infection))
+    Ok(warp::reply::json(&updated_infection))
 }
 
-async fn get_all(
+async fn delete(
+    infection_id: Uuid,
     professional: ProfessionalUser,
+    query: InfectionRetractionQuery,
     context: Context,
 ) -> Result<impl Reply, Rejection> {
     let connector = context.builder.create();
 
-    let infections: Vec<Infection> =
-        infection::get_all_with_organization(&connector, &professional.organization.id)?
-            .into_iter()
-            .map(|i| i.into())
-            .collect();
+    let (infection, organization) = infection::get_with_organization(&connector, &infection_id)?;
+
+    if organization.id != professional.organization.id {
+        return Err(warp::reject::not_found());
+    }
+
+    if infection.reported_by.is_some() {
+        return Err(warp::reject::custom(Error::SelfReportedInfection));
+    }
+
+    infection::delete(&connector, &infection_id, query.notify.unwrap_or(false))?;
+
+    Ok(warp::reply())
+}
+
+async fn get_all_review(
+    authority: HealthAuthorityUser,
+    query: InfectionReviewQuery,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    let places_ids = match &authority.region {
+        Some(region) => Some(place::get_ids_with_region(&connector, region)?),
+        None => None,
+    };
+
+    let infections = infection::get_all_with_status(
+        &connector,
+        query.status.unwrap_or(InfectionStatus::Pending).into(),
+        places_ids.as_ref(),
+    )?;
+
+    let infections_ids: Vec<Uuid> = infections.iter().map(|(i, _)| i.id).collect();
+    let mut stats = notification::get_stats_with_infections(&connector, &infections_ids)?;
+
+    let infections: Vec<Infection> = infections
+        .into_iter()
+        .map(|(i, o)| {
+            let notifications = stats.remove(&i.id).unwrap_or_default();
+            (i, o, notifications).into()
+        })
+        .collect();
 
     Ok(warp::reply::json(&infections))
 }
+
+async fn approve(
+    infection_id: Uuid,
+    authority: HealthAuthorityUser,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    validate_in_region(&connector, &authority, &infection_id)?;
+
+    // Approve infection, warnings and the organization summary are queued for delivery
+    infection::approve(&connector, &infection_id, &authority.user.id)?;
+
+    let approved_infection = get_with_notifications(&connector, &infection_id)?;
+
+    Ok(warp::reply::json(&approved_infection))
+}
+
+async fn reject(
+    infection_id: Uuid,
+    authority: HealthAuthorityUser,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    validate_in_region(&connector, &authority, &infection_id)?;
+
+    infection::reject(&connector, &infection_id, &authority.user.id)?;
+
+    let rejected_infection = get_with_notifications(&connector, &infection_id)?;
+
+    Ok(warp::reply::json(&rejected_infection))
+}
+
+/// Check that the infection involves a place of the region reviewed by the authority
+fn validate_in_region(
+    connector: &Connector,
+    authority: &HealthAuthorityUser,
+    infection_id: &Uuid,
+) -> Result<(), Rejection> {
+    let (infection, _) = infection::get_with_organization(connector, infection_id)?;
+
+    if let Some(region) = &authority.region {
+        let places_ids = place::get_ids_with_region(connector, region)?;
+
+        if !infection
+            .places_ids
+            .iter()
+            .any(|id| places_ids.contains(id))
+        {
+            return Err(warp::reject::not_found());
+        }
+    }
+
+    Ok(())
+}
+
+fn get_with_notifications(
+    connector: &Connector,
+    infection_id: &Uuid,
+) -> Result<Infection, Rejection> {
+    let (infection, organization) = infection::get_with_organization(connector, infection_id)?;
+
+    let notifications = notification::get_stats_with_infections(connector, &vec![infection.id])?
+        .remove(&infection.id)
+        .unwrap_or_default();
+
+    Ok((infection, organization, notifications).into())
+}
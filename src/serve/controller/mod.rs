@@ -5,3 +5,5 @@ pub mod infection;
 pub mod organization;
 pub mod place;
 pub mod profile;
+pub mod push;
+pub mod statistics;
@@ -1,9 +1,13 @@
-use super::super::authorization::professional_user_filter;
+use super::super::authorization::{health_authority_user_filter, professional_user_filter};
 use super::super::error::Error;
 use super::super::query::query_qs;
 use super::super::types::*;
-use crate::model::place;
 use crate::model::types::GaugeLevel as GaugeLevelModel;
+use crate::model::{occupancy, opening_hour, place};
+use chrono::{Datelike, Duration, NaiveTime, Timelike, Utc};
+use futures::{stream, StreamExt};
+use std::collections::HashSet;
+use tokio::sync::broadcast::error::RecvError;
 use uuid::Uuid;
 use validator::Validate;
 use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
@@ -25,6 +29,14 @@ pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
         .and(context_filter.clone())
         .and_then(get_one_owned);
 
+    // GET /place/owned/<id>/occupancy?from=&to=&bucket=hour -> Vec<Occupancy>
+    let get_owned_place_occupancy = warp::get()
+        .and(warp::path!("place" / "owned" / Uuid / "occupancy"))
+        .and(professional_user_filter(context.clone()))
+        .and(query_qs())
+        .and(context_filter.clone())
+        .and_then(get_occupancy);
+
     // GET /places -> Vec<OwnedPlace>
     let get_places = warp::get()
         .and(warp::path!("places"))
@@ -64,13 +76,48 @@ pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
         .and(context_filter.clone())
         .and_then(delete);
 
+    // GET /place/<id>/opening-hours -> OpeningHours
+    let get_opening_hours = warp::get()
+        .and(warp::path!("place" / Uuid / "opening-hours"))
+        .and(context_filter.clone())
+        .and_then(get_opening_hours);
+
+    // PUT /place/<id>/opening-hours {days, dates} -> OpeningHours
+    let set_opening_hours = warp::put()
+        .and(warp::path!("place" / Uuid / "opening-hours"))
+        .and(professional_user_filter(context.clone()))
+        .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
+        .and(warp::body::json())
+        .and(context_filter.clone())
+        .and_then(update_opening_hours);
+
+    // PUT /place/<id>/region {region} -> Place
+    let set_region = warp::put()
+        .and(warp::path!("place" / Uuid / "region"))
+        .and(health_authority_user_filter(context.clone()))
+        .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
+        .and(warp::body::json())
+        .and(context_filter.clone())
+        .and_then(update_region);
+
+    // GET /place/<id>/gauge/stream -> SSE PlaceGauge
+    let stream_place_gauge = warp::get()
+        .and(warp::path!("place" / Uuid / "gauge" / "stream"))
+        .and(context_filter.clone())
+        .and_then(stream_gauge);
+
     get_place
         .or(get_owned_place)
+        .or(get_owned_place_occupancy)
         .or(get_places)
         .or(search_places)
         .or(create_place)
         .or(set_place)
         .or(delete_place)
+        .or(get_opening_hours)
+        .or(set_opening_hours)
+        .or(set_region)
+        .or(stream_place_gauge)
         .boxed()
 }
 
@@ -82,6 +129,74 @@ async fn get_one(place_id: Uuid, context: Context) -> Result<impl Reply, Rejecti
     Ok(warp::reply::json(&place))
 }
 
+async fn update_region(
+    place_id: Uuid,
+    authority: HealthAuthorityUser,
+    data: PlaceRegionForm,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    // Validate data
+    if let Err(errors) = data.validate() {
+        return Err(warp::reject::custom(Error::InvalidDataWithDetails {
+            source: errors,
+        }));
+    }
+
+    // Only authorities reviewing all regions assign them
+    if authority.region.is_some() {
+        return Err(warp::reject::custom(Error::Unauthorized));
+    }
+
+    let connector = context.builder.create();
+
+    place::set_region(&connector, &place_id, data.region)?;
+
+    let place: Place = place::get_with_organization(&connector, &place_id)?.into();
+
+    Ok(warp::reply::json(&place))
+}
+
+async fn stream_gauge(place_id: Uuid, context: Context) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    // Subscribe before reading the place to miss no update
+    let receiver = connector.gauge.subscribe();
+    let gauge: PlaceGauge = place::get(&connector, &place_id)?.into();
+    let initial_event = gauge_event(&gauge);
+
+    // Push the gauge again each time it changes
+    let updates = stream::unfold(
+        (connector, receiver, gauge),
+        move |(connector, mut receiver, last_gauge)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(id) if id != place_id => continue,
+                    Ok(_) | Err(RecvError::Lagged(_)) => {
+                        let gauge: PlaceGauge = match place::get(&connector, &place_id) {
+                            Ok(place) => place.into(),
+                            Err(_) => return None,
+                        };
+
+                        if gauge != last_gauge {
+                            let event = gauge_event(&gauge);
+                            return Some((event, (connector, receiver, gauge)));
+                        }
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+
+    let events = stream::once(async { initial_event }).chain(updates);
+
+    Ok(warp::sse::reply(warp::sse::keep_alive().stream(events)))
+}
+
+fn gauge_event(gauge: &PlaceGauge) -> Result<warp::sse::Event, serde_json::Error> {
+    warp::sse::Event::default().event("gauge").json_data(gauge)
+}
+
 async fn get_one_owned(
     place_id: Uuid,
     professional: ProfessionalUser,
@@ -98,6 +213,45 @@ async fn get_one_owned(
     Ok(warp::reply::json(&place))
 }
 
+async fn get_occupancy(
+    place_id: Uuid,
+    professional: ProfessionalUser,
+    query: OccupancyQuery,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    // Limit the number of buckets
+    let maximum_range = match query.bucket {
+        OccupancyBucket::Hour => Duration::days(31),
+        OccupancyBucket::Day => Duration::days(366),
+    };
+
+    if query.from >= query.to || query.to - query.from > maximum_range {
+        return Err(warp::reject::custom(Error::InvalidData));
+    }
+
+    let connector = context.builder.create();
+
+    let (place, organization) = place::get_with_organization(&connector, &place_id)?;
+
+    if organization.id != professional.organization.id {
+        return Err(warp::reject::not_found());
+    }
+
+    let occupancies: Vec<Occupancy> = occupancy::get_all_with_place(
+        &connector,
+        &place.id,
+        &place.timezone,
+        &query.from,
+        &query.to,
+        &query.bucket.into(),
+    )?
+    .into_iter()
+    .map(|o| o.into())
+    .collect();
+
+    Ok(warp::reply::json(&occupancies))
+}
+
 async fn get_all(
     professional: ProfessionalUser,
     context: Context,
@@ -127,12 +281,18 @@ async fn search(query: PlaceSearchQuery, context: Context) -> Result<impl Reply,
         ],
     };
 
+    let open_at = match (query.open_now, query.open_at) {
+        (Some(true), _) => Some(Utc::now()),
+        (_, open_at) => open_at,
+    };
+
     let (pagination, places) = place::search(
         &connector,
         query.location.into(),
         query.radius,
         gauge_levels,
-        query.pagination.into(),
+        open_at,
+        query.pagination,
     )?;
     let results = PlacesSearchResults {
         pagination,
@@ -154,6 +314,10 @@ async fn create(
         }));
     }
 
+    if !is_valid_gauge_percents(&data) {
+        return Err(warp::reject::custom(Error::InvalidData));
+    }
+
     let connector = context.builder.create();
 
     // Create place
@@ -169,6 +333,9 @@ async fn create(
             maximum_duration: data.maximum_duration,
             location: data.location.map(|location| location.into()),
             timezone: data.timezone.into(),
+            warning_percent: data.warning_percent,
+            alert_percent: data.alert_percent,
+            minimum_overlap: data.minimum_overlap,
         },
     )?;
 
@@ -191,6 +358,10 @@ async fn update(
         }));
     }
 
+    if !is_valid_gauge_percents(&data) {
+        return Err(warp::reject::custom(Error::InvalidData));
+    }
+
     let connector = context.builder.create();
 
     // Update place
@@ -207,15 +378,28 @@ async fn update(
             maximum_duration: data.maximum_duration,
             location: data.location.map(|location| location.into()),
             timezone: data.timezone.into(),
+            warning_percent: data.warning_percent,
+            alert_percent: data.alert_percent,
+            minimum_overlap: data.minimum_overlap,
         },
     )?;
 
+    // Compute opening timestamps with the new timezone
+    opening_hour::refresh_computed_with_place(&connector, &place_id)?;
+
     // Retrieve updated place
     let place: OwnedPlace = place::get_with_organization(&connector, &place_id)?.into();
 
     Ok(warp::reply::json(&place))
 }
 
+fn is_valid_gauge_percents(data: &PlaceForm) -> bool {
+    match (data.warning_percent, data.alert_percent) {
+        (Some(warning_percent), Some(alert_percent)) => warning_percent <= alert_percent,
+        _ => true,
+    }
+}
+
 async fn delete(
     place_id: Uuid,
     professional: ProfessionalUser,
@@ -228,3 +412,190 @@ async fn delete(
 
     Ok(warp::reply())
 }
+
+async fn get_opening_hours(place_id: Uuid, context: Context) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    // Check if place exists
+    place::get(&connector, &place_id)?;
+
+    let opening_hours: OpeningHours =
+        opening_hour::get_all_with_place(&connector, &place_id)?.into();
+
+    Ok(warp::reply::json(&opening_hours))
+}
+
+async fn update_opening_hours(
+    place_id: Uuid,
+    professional: ProfessionalUser,
+    data: OpeningHoursForm,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    // Validate data
+    if let Err(errors) = data.validate() {
+        return Err(warp::reject::custom(Error::InvalidDataWithDetails {
+            source: errors,
+        }));
+    }
+
+    let connector = context.builder.create();
+
+    // Check place ownership
+    let (place, organization) = place::get_with_organization(&connector, &place_id)?;
+
+    if organization.id != professional.organization.id {
+        return Err(warp::reject::not_found());
+    }
+
+    if !is_valid_opening_hours(&data) {
+        return Err(warp::reject::custom(Error::InvalidData));
+    }
+
+    // Dates are expressed in the place timezone, past ones are dropped
+    let today = Utc::now()
+        .with_timezone(&place.timezone.tz)
+        .date()
+        .naive_local();
+
+    // Replace opening hours
+    opening_hour::replace_all_with_place(
+        &connector,
+        &place_id,
+        &data
+            .days
+            .into_iter()
+            .map(|day| opening_hour::OpeningHourDayInsert {
+                place_id,
+                day: day.day,
+                opening_time: day.opening_time,
+                closure_time: day.closure_time,
+                evacuation_time: day.evacuation_time,
+            })
+            .collect(),
+        &data
+            .dates
+            .into_iter()
+            .filter(|date| date.date >= today)
+            .map(|date| opening_hour::OpeningHourDateInsert {
+                place_id,
+                date: date.date,
+                opening_time: date.opening_time,
+                closure_time: date.closure_time,
+                evacuation_time: date.evacuation_time,
+                closed: date.closed,
+            })
+            .collect(),
+    )?;
+
+    // Compute opening timestamps
+    opening_hour::refresh_computed_with_place(&connector, &place_id)?;
+
+    // Retrieve updated opening hours
+    let opening_hours: OpeningHours =
+        opening_hour::get_all_with_place(&connector, &place_id)?.into();
+
+    Ok(warp::reply::json(&opening_hours))
+}
+
+fn is_valid_opening_hours(data: &OpeningHoursForm) -> bool {
+    let valid_days = data.days.iter().all(|day| {
+        opening_hour::is_valid_time_span(&day.opening_time, &day.closure_time, &day.evacuation_time)
+    });
+
+    let valid_dates = data.dates.iter().all(|date| {
+        match (
+            date.closed,
+            date.opening_time,
+            date.closure_time,
+            date.evacuation_time,
+        ) {
+            (true, None, None, None) => true,
+            (false, Some(opening_time), Some(closure_time), Some(evacuation_time)) => {
+                opening_hour::is_valid_time_span(&opening_time, &closure_time, &evacuation_time)
+            }
+            _ => false,
+        }
+    });
+
+    if !valid_days || !valid_dates {
+        return false;
+    }
+
+    // Weekly spans must not overlap, the week wrapping around
+    let day_spans = data
+        .days
+        .iter()
+        .map(|day| {
+            span_minutes(
+                i64::from(day.day - 1),
+                &day.opening_time,
+                &day.closure_time,
+                &day.evacuation_time,
+            )
+        })
+        .collect();
+
+    if has_overlapping_spans(day_spans, Some(7 * MINUTES_PER_DAY)) {
+        return false;
+    }
+
+    // A closed date must be alone, and open spans of dates must not overlap
+    let mut closed_dates = HashSet::new();
+    let mut open_dates = HashSet::new();
+    let mut date_spans = Vec::new();
+
+    for date in data.dates.iter() {
+        match (date.opening_time, date.closure_time, date.evacuation_time) {
+            (Some(opening_time), Some(closure_time), Some(evacuation_time)) => {
+                open_dates.insert(date.date);
+                date_spans.push(span_minutes(
+                    i64::from(date.date.num_days_from_ce()),
+                    &opening_time,
+                    &closure_time,
+                    &evacuation_time,
+                ));
+            }
+            _ => {
+                if !closed_dates.insert(date.date) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    closed_dates.is_disjoint(&open_dates) && !has_overlapping_spans(date_spans, None)
+}
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+/// Start and evacuation minutes of a span opening on the given day
+fn span_minutes(
+    day: i64,
+    opening_time: &NaiveTime,
+    closure_time: &NaiveTime,
+    evacuation_time: &NaiveTime,
+) -> (i64, i64) {
+    let (open_duration, evacuation_delay) =
+        opening_hour::time_span(opening_time, closure_time, evacuation_time);
+    let start = day * MINUTES_PER_DAY + i64::from(opening_time.num_seconds_from_midnight()) / 60;
+
+    (
+        start,
+        start + (open_duration + evacuation_delay).num_minutes(),
+    )
+}
+
+/// Check whether a span starts before the evacuation of the previous one,
+/// the last span being compared with the first one of the next period if any
+fn has_overlapping_spans(mut spans: Vec<(i64, i64)>, period: Option<i64>) -> bool {
+    spans.sort_unstable();
+
+    let overlapping = spans.windows(2).any(|pair| pair[1].0 < pair[0].1);
+
+    match (period, spans.first(), spans.last()) {
+        (Some(period), Some(first), Some(last)) if spans.len() > 1 => {
+            overlapping || first.0 + period < last.1
+        }
+        _ => overlapping,
+    }
+}
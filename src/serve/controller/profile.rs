@@ -1,8 +1,14 @@
 use super::super::authorization::public_user_filter;
+use super::super::error::Error;
 use super::super::types::*;
-use crate::model::user;
+use crate::model::{exposure, infection, user};
+use chrono::{DateTime, Duration, Utc};
+use std::cmp::min;
 use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
 
+/// Maximum number of days since the symptom onset to report a positive test
+const MAXIMUM_SYMPTOM_ONSET_DAYS: i64 = 30;
+
 pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
     let moved_context = context.clone();
     let context_filter = warp::any().map(move || moved_context.clone());
@@ -21,7 +27,27 @@ pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
         .and(context_filter.clone())
         .and_then(delete);
 
-    get_profile.or(delete_profile).boxed()
+    // POST /profile/infection {symptomOnsetDate} -> InfectionReport
+    let report_infection = warp::post()
+        .and(warp::path!("profile" / "infection"))
+        .and(public_user_filter(context.clone()))
+        .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
+        .and(warp::body::json())
+        .and(context_filter.clone())
+        .and_then(report_infection);
+
+    // GET /profile/exposure -> ExposureSummary
+    let get_exposure = warp::get()
+        .and(warp::path!("profile" / "exposure"))
+        .and(public_user_filter(context.clone()))
+        .and(context_filter.clone())
+        .and_then(get_exposure);
+
+    get_profile
+        .or(delete_profile)
+        .or(report_infection)
+        .or(get_exposure)
+        .boxed()
 }
 
 async fn get(public: PublicUser, context: Context) -> Result<impl Reply, Rejection> {
@@ -37,3 +63,46 @@ async fn delete(public: PublicUser, context: Context) -> Result<impl Reply, Reje
 
     Ok(warp::reply())
 }
+
+async fn get_exposure(public: PublicUser, context: Context) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    let summary: ExposureSummary = exposure::get_all_with_user(&connector, &public.user.id)?.into();
+
+    Ok(warp::reply::json(&summary))
+}
+
+async fn report_infection(
+    public: PublicUser,
+    data: InfectionReportForm,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    let today = Utc::today().naive_utc();
+
+    // Validate symptom onset date
+    if data.symptom_onset_date > today
+        || data.symptom_onset_date < today - Duration::days(MAXIMUM_SYMPTOM_ONSET_DAYS)
+    {
+        return Err(warp::reject::custom(Error::InvalidData));
+    }
+
+    let connector = context.builder.create();
+    let configuration = &connector.configuration.infection;
+
+    // Derive contagious period from symptom onset
+    let symptom_onset = DateTime::<Utc>::from_utc(data.symptom_onset_date.and_hms(0, 0, 0), Utc);
+    let start_timestamp = symptom_onset - Duration::days(configuration.contagious_days_before);
+    let end_timestamp = min(
+        symptom_onset + Duration::days(configuration.contagious_days_after + 1),
+        Utc::now(),
+    );
+
+    let exposures_count = infection::insert_reported(
+        &connector,
+        &public.user.id,
+        &start_timestamp,
+        &end_timestamp,
+    )?;
+
+    Ok(warp::reply::json(&InfectionReport { exposures_count }))
+}
@@ -0,0 +1,84 @@
+use super::super::authorization::public_user_filter;
+use super::super::error::Error;
+use super::super::types::*;
+use crate::model::push_subscription;
+use validator::Validate;
+use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
+
+pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
+    let moved_context = context.clone();
+    let context_filter = warp::any().map(move || moved_context.clone());
+
+    // GET /push/key -> PushKey
+    let get_key = warp::get()
+        .and(warp::path!("push" / "key"))
+        .and(context_filter.clone())
+        .and_then(get_key);
+
+    // PUT /session/push-subscription {endpoint, keys} -> 200
+    let subscribe = warp::put()
+        .and(warp::path!("session" / "push-subscription"))
+        .and(public_user_filter(context.clone()))
+        .and(warp::body::content_length_limit(CONTENT_LENGTH_LIMIT))
+        .and(warp::body::json())
+        .and(context_filter.clone())
+        .and_then(subscribe);
+
+    // DELETE /session/push-subscription -> 200
+    let unsubscribe = warp::delete()
+        .and(warp::path!("session" / "push-subscription"))
+        .and(public_user_filter(context.clone()))
+        .and(context_filter.clone())
+        .and_then(unsubscribe);
+
+    get_key.or(subscribe).or(unsubscribe).boxed()
+}
+
+async fn get_key(context: Context) -> Result<impl Reply, Rejection> {
+    let connector = context.builder.create();
+
+    match connector.push.public_key() {
+        Some(public_key) => Ok(warp::reply::json(&PushKey {
+            public_key: public_key.to_string(),
+        })),
+        None => Err(warp::reject::not_found()),
+    }
+}
+
+async fn subscribe(
+    public: PublicUser,
+    data: PushSubscriptionForm,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    // Validate data
+    if let Err(errors) = data.validate() {
+        return Err(warp::reject::custom(Error::InvalidDataWithDetails {
+            source: errors,
+        }));
+    }
+
+    let connector = context.builder.create();
+
+    if connector.push.validate_endpoint(&data.endpoint).is_err() {
+        return Err(warp::reject::custom(Error::InvalidData));
+    }
+
+    push_subscription::upsert(
+        &connector,
+        &push_subscription::PushSubscriptionUpsert {
+            session_id: public.session.id,
+            user_id: public.user.id,
+            endpoint: data.endpoint,
+            p256dh: data.keys.p256dh,
+            auth: data.keys.auth,
+        },
+    )?;
+
+    Ok(warp::reply())
+}
+
+async fn unsubscribe(public: PublicUser, context: Context) -> Result<impl Reply, Rejection> {
+    push_subscription::delete_with_session(&context.builder.create(), &public.session.id)?;
+
+    Ok(warp::reply())
+}
@@ -0,0 +1,48 @@
+use super::super::authorization::health_authority_user_filter;
+use super::super::error::Error;
+use super::super::query::query_qs;
+use super::super::types::*;
+use crate::model::statistics;
+use chrono::Duration;
+use warp::{filters::BoxedFilter, Filter, Rejection, Reply};
+
+pub fn routes(context: Context) -> BoxedFilter<(impl Reply,)> {
+    let moved_context = context.clone();
+    let context_filter = warp::any().map(move || moved_context.clone());
+
+    // GET /statistics/infections?from=&to=&groupBy=day -> InfectionStatisticsSummary
+    let get_infection_statistics = warp::get()
+        .and(warp::path!("statistics" / "infections"))
+        .and(health_authority_user_filter(context.clone()))
+        .and(query_qs())
+        .and(context_filter.clone())
+        .and_then(get_infections);
+
+    get_infection_statistics.boxed()
+}
+
+async fn get_infections(
+    authority: HealthAuthorityUser,
+    query: StatisticsQuery,
+    context: Context,
+) -> Result<impl Reply, Rejection> {
+    if query.from >= query.to || query.to - query.from > Duration::days(366) {
+        return Err(warp::reject::custom(Error::InvalidData));
+    }
+
+    let connector = context.builder.create();
+
+    let (aggregated, suppressed_count) = statistics::get_all_aggregated(
+        &connector,
+        query.group_by.into(),
+        authority.region.as_ref(),
+        &query.from,
+        &query.to,
+    )?;
+
+    Ok(warp::reply::json(&InfectionStatisticsSummary {
+        k_anonymity: connector.configuration.statistics.k_anonymity,
+        suppressed_count,
+        statistics: aggregated.into_iter().map(|s| s.into()).collect(),
+    }))
+}
@@ -3,6 +3,7 @@ use serde::Serialize;
 use std::convert::Infallible;
 use tracing::error;
 use validator::ValidationErrors;
+use warp::http::header::{self, HeaderValue};
 use warp::http::StatusCode;
 use warp::{reject, Rejection, Reply};
 
@@ -10,15 +11,18 @@ custom_error! { pub Error
     InvalidData = "Invalid data",
     InvalidDataWithDetails {source: ValidationErrors} = "Invalid data: {source}",
     Unauthorized = "Unauthorized",
-    ModelError {source: crate::model::error::Error} = "[Model] {source}",
+    Model {source: crate::model::error::Error} = "[Model] {source}",
     MaximumGaugeReached = "Gauge alert level reached, come back later",
+    PlaceClosed = "Place is closed, come back later",
+    SelfReportedInfection = "Self-reported infection, only health authorities can retract it",
+    TooManyRequests {retry_after: i64} = "Too many requests, retry in {retry_after} seconds",
 }
 
 impl reject::Reject for Error {}
 
 impl From<crate::model::error::Error> for Rejection {
     fn from(error: crate::model::error::Error) -> Self {
-        warp::reject::custom(Error::ModelError { source: error })
+        warp::reject::custom(Error::Model { source: error })
     }
 }
 
@@ -31,9 +35,10 @@ struct ErrorResponse {
 struct InternalErrorResponse {
     code: StatusCode,
     message: String,
+    retry_after: Option<i64>,
 }
 
-/// Handle all rejctions and returns 400, 401, 404 and 500
+/// Handle all rejctions and returns 400, 401, 404, 429 and 500
 pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
     let response: InternalErrorResponse;
 
@@ -45,19 +50,27 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
                 Error::InvalidData => StatusCode::BAD_REQUEST,
                 Error::InvalidDataWithDetails { .. } => StatusCode::BAD_REQUEST,
                 Error::Unauthorized => StatusCode::UNAUTHORIZED,
-                Error::ModelError { source } => match source {
+                Error::Model { source } => match source {
                     crate::model::error::Error::NotFound => StatusCode::NOT_FOUND,
                     _ => StatusCode::INTERNAL_SERVER_ERROR,
                 },
                 Error::MaximumGaugeReached => StatusCode::FORBIDDEN,
+                Error::PlaceClosed => StatusCode::FORBIDDEN,
+                Error::SelfReportedInfection => StatusCode::FORBIDDEN,
+                Error::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
             },
             message: error.to_string(),
+            retry_after: match error {
+                Error::TooManyRequests { retry_after } => Some(*retry_after),
+                _ => None,
+            },
         };
     } else if let Some(missing_header) = err.find::<warp::reject::MissingHeader>() {
         if missing_header.name() == "authorization" {
             response = InternalErrorResponse {
                 code: StatusCode::UNAUTHORIZED,
                 message: String::from("Unauthorized"),
+                retry_after: None,
             };
         } else {
             error!("Missing header: {:?}", missing_header);
@@ -65,6 +78,7 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
             response = InternalErrorResponse {
                 code: StatusCode::BAD_REQUEST,
                 message: String::from("Bad request"),
+                retry_after: None,
             };
         }
     } else if let Some(body_error) = err.find::<warp::body::BodyDeserializeError>() {
@@ -73,14 +87,16 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
         response = InternalErrorResponse {
             code: StatusCode::BAD_REQUEST,
             message: String::from("Bad request"),
+            retry_after: None,
         };
-    } else if let Some(_) = err.find::<warp::reject::MethodNotAllowed>() {
+    } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
         error!("Maybe not found error: {:?}", err);
 
         // Must be a not found response
         response = InternalErrorResponse {
             code: StatusCode::NOT_FOUND,
             message: String::from("Not found"),
+            retry_after: None,
         };
     } else {
         error!("Unhandled error: {:?}", err);
@@ -88,14 +104,24 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
         response = InternalErrorResponse {
             code: StatusCode::INTERNAL_SERVER_ERROR,
             message: String::from("Internal server error"),
+            retry_after: None,
         };
     }
 
-    Ok(warp::reply::with_status(
+    let mut reply = warp::reply::with_status(
         warp::reply::json(&ErrorResponse {
             code: response.code.as_u16(),
             message: response.message,
         }),
         response.code,
-    ))
+    )
+    .into_response();
+
+    if let Some(retry_after) = response.retry_after {
+        reply
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    }
+
+    Ok(reply)
 }
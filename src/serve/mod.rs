@@ -2,12 +2,15 @@ mod authorization;
 mod controller;
 mod error;
 mod query;
+mod rate_limit;
+mod scheduler;
 mod session;
 mod types;
 
 use crate::connector::ConnectorBuilder;
+use crate::task::TaskType;
 use error::handle_rejection;
-use std::{env, net::SocketAddr};
+use std::{env, net::SocketAddr, time::Duration};
 use tracing::info;
 use types::Context;
 use warp::{http::header, http::Method, Filter};
@@ -20,6 +23,64 @@ pub async fn run(builder: ConnectorBuilder) {
         .parse()
         .expect("Invalid LISTEN");
 
+    // Start scheduled tasks
+    let scheduled_tasks = [
+        (
+            TaskType::RefreshAllGauges,
+            "REFRESH_ALL_GAUGES_INTERVAL",
+            60,
+        ),
+        (
+            TaskType::CleanGaugeSnapshots,
+            "CLEAN_GAUGE_SNAPSHOTS_INTERVAL",
+            86400,
+        ),
+        (
+            TaskType::ComputeOpeningHours,
+            "COMPUTE_OPENING_HOURS_INTERVAL",
+            3600,
+        ),
+        (
+            TaskType::LeaveEvacuatedCheckins,
+            "AUTO_CHECKOUT_INTERVAL",
+            60,
+        ),
+        (
+            TaskType::SendNotifications,
+            "SEND_NOTIFICATIONS_INTERVAL",
+            30,
+        ),
+        (
+            TaskType::SendCheckinReminders,
+            "SEND_CHECKIN_REMINDERS_INTERVAL",
+            60,
+        ),
+        (
+            TaskType::CleanExpiredSessions,
+            "CLEAN_EXPIRED_SESSIONS_INTERVAL",
+            3600,
+        ),
+        (TaskType::ExportStatistics, "EXPORT_STATISTICS_INTERVAL", 0),
+    ];
+
+    for (task_type, variable, default_interval) in scheduled_tasks.iter() {
+        let interval: u64 = env::var(variable)
+            .map(|interval_str| {
+                interval_str
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid {}", variable))
+            })
+            .unwrap_or(*default_interval);
+
+        if interval > 0 {
+            tokio::spawn(scheduler::schedule(
+                builder.clone(),
+                *task_type,
+                Duration::from_secs(interval),
+            ));
+        }
+    }
+
     // Prepare Context
     let context = Context { builder };
 
@@ -44,6 +105,8 @@ pub async fn run(builder: ConnectorBuilder) {
         .or(controller::organization::routes(context.clone()))
         .or(controller::checkin::routes(context.clone()))
         .or(controller::infection::routes(context.clone()))
+        .or(controller::statistics::routes(context.clone()))
+        .or(controller::push::routes(context.clone()))
         .recover(handle_rejection)
         .with(cors);
 
@@ -0,0 +1,74 @@
+use super::error::Error;
+use super::types::Context;
+use crate::connector::Connector;
+use crate::model::rate_limit;
+use chrono::Utc;
+use std::net::{IpAddr, SocketAddr};
+use warp::{reject, Filter, Rejection};
+
+/// Limit the requests of each IP address to an endpoint identified by its scope
+pub fn ip_rate_limit_filter(
+    context: Context,
+    scope: &'static str,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .map(move |remote, forwarded_for| (remote, forwarded_for, context.clone()))
+        .and_then(
+            move |(remote, forwarded_for, context): (
+                Option<SocketAddr>,
+                Option<String>,
+                Context,
+            )| async move {
+                let connector = context.builder.create();
+                let maximum = connector.configuration.rate_limit.ip_requests;
+
+                let ip = if connector.configuration.rate_limit.trust_forwarded_for {
+                    forwarded_for.as_deref().and_then(last_forwarded_ip)
+                } else {
+                    None
+                }
+                .or_else(|| remote.map(|address| address.ip()));
+
+                match ip {
+                    Some(ip) => check(&connector, &format!("ip:{}:{}", scope, ip), maximum),
+                    None => Ok(()),
+                }
+            },
+        )
+        .untuple_one()
+}
+
+/// Limit the confirmation emails sent to a login
+pub fn check_login_rate_limit(connector: &Connector, login: &str) -> Result<(), Rejection> {
+    check(
+        connector,
+        &format!("login:{}", login),
+        connector.configuration.rate_limit.login_requests,
+    )
+}
+
+fn check(connector: &Connector, key: &str, maximum: i64) -> Result<(), Rejection> {
+    if maximum <= 0 {
+        return Ok(());
+    }
+
+    let hit = rate_limit::hit(connector, key)?;
+
+    if i64::from(hit.hits) <= maximum {
+        return Ok(());
+    }
+
+    let elapsed = (Utc::now() - hit.window_start).num_seconds();
+    let retry_after = (connector.configuration.rate_limit.window - elapsed).max(1);
+
+    Err(reject::custom(Error::TooManyRequests { retry_after }))
+}
+
+/// The last address is the one appended by the trusted reverse proxy
+fn last_forwarded_ip(forwarded_for: &str) -> Option<IpAddr> {
+    forwarded_for
+        .rsplit(',')
+        .next()
+        .and_then(|ip| ip.trim().parse().ok())
+}
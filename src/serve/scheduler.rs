@@ -0,0 +1,101 @@
+use crate::connector::ConnectorBuilder;
+use crate::model::lock;
+use crate::task::TaskType;
+use chrono::{DateTime, TimeZone, Utc};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::runtime::Handle;
+use tracing::{debug, error, info};
+
+/// Periodically execute the task, once per tick across all replicas
+///
+/// Ticks are aligned on the wall clock so that replicas sharing the same
+/// interval compete for the advisory lock at the same time. The tick is then
+/// recorded under the lock so that a replica firing later skips it.
+pub async fn schedule(builder: ConnectorBuilder, task_type: TaskType, period: Duration) {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let delay = period.as_millis() - since_epoch.as_millis() % period.as_millis();
+    let start = tokio::time::Instant::now() + Duration::from_millis(delay as u64);
+
+    let mut interval = tokio::time::interval_at(start, period);
+
+    info!("Task {:?} scheduled every {:?}", task_type, period);
+
+    loop {
+        interval.tick().await;
+
+        let builder = builder.clone();
+        let tick_timestamp = current_tick(period);
+        let handle = Handle::current();
+
+        // Database accesses are blocking, run them away from the workers serving HTTP
+        let execution = tokio::task::spawn_blocking(move || {
+            run_tick(builder, task_type, tick_timestamp, handle)
+        })
+        .await;
+
+        if let Err(error) = execution {
+            error!("Task {:?} aborted: {}", task_type, error);
+        }
+    }
+}
+
+fn run_tick(
+    builder: ConnectorBuilder,
+    task_type: TaskType,
+    tick_timestamp: DateTime<Utc>,
+    handle: Handle,
+) {
+    let connector = builder.create();
+
+    let lock = match lock::try_advisory_lock(&connector, task_type.lock_key()) {
+        Ok(Some(lock)) => lock,
+        Ok(None) => {
+            debug!("Task {:?} skipped, running elsewhere", task_type);
+            return;
+        }
+        Err(error) => {
+            error!("Task {:?} not started: {}", task_type, error);
+            return;
+        }
+    };
+
+    match lock.claim_tick(&tick_timestamp) {
+        Ok(true) => (),
+        Ok(false) => {
+            debug!("Task {:?} skipped, already run for this tick", task_type);
+            return;
+        }
+        Err(error) => {
+            error!("Task {:?} not started: {}", task_type, error);
+            return;
+        }
+    }
+
+    let started_at = Instant::now();
+    let result = handle.block_on(task_type.execute(&connector));
+    let duration = started_at.elapsed();
+
+    drop(lock);
+
+    match result {
+        Ok(_) => info!("Task {:?} completed in {:?}", task_type, duration),
+        Err(error) => error!(
+            "Task {:?} failed after {:?}: {}",
+            task_type, duration, error
+        ),
+    }
+}
+
+/// Wall clock tick nearest to now, identical on replicas with slightly shifted clocks
+fn current_tick(period: Duration) -> DateTime<Utc> {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let period = period.as_millis();
+    let tick = (since_epoch + period / 2) / period * period;
+
+    Utc.timestamp_millis(tick as i64)
+}
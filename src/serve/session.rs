@@ -1,8 +1,9 @@
 use super::types::{RedirectPage, Session};
-use crate::connector::{email::template::DeviceValidationEmail, Connector};
+use crate::connector::email::template::{DeviceValidationEmail, Locale};
+use crate::connector::Connector;
 use crate::model::error::Error;
-use crate::model::session;
-use crate::security::{generate_token, hash};
+use crate::model::{session, user};
+use crate::security::{generate_token, hash_token};
 use uuid::Uuid;
 
 pub async fn create_session(
@@ -11,15 +12,19 @@ pub async fn create_session(
     email_address: String,
     description: String,
     redirect_page: RedirectPage,
+    locale: Locale,
 ) -> Result<Session, Error> {
     // Create session with confirmation token
     let token = generate_token();
     let session: Session = session::insert(
-        &connector,
+        connector,
         &session::SessionInsert {
             user_id,
             description,
-            hashed_confirmation_token: hash(token.clone()),
+            hashed_confirmation_token: hash_token(
+                &connector.configuration.security.login_pepper,
+                &token,
+            ),
         },
     )?
     .into();
@@ -30,6 +35,7 @@ pub async fn create_session(
         }
         RedirectPage::Checkins => String::from("redirect=checkins"),
         RedirectPage::Places => String::from("redirect=places"),
+        RedirectPage::Infections => String::from("redirect=infections"),
     };
 
     // Send validation URL
@@ -41,16 +47,31 @@ pub async fn create_session(
                 "/validate-session/?sessionId={}&token={}&{}",
                 session.id, token, redirect
             ),
+            locale,
         }])
         .await;
 
     Ok(session)
 }
 
-pub fn get_auth_from_email(email: String) -> (String, String) {
-    // Hash email to get login
-    let cleaned_email = email.to_lowercase();
-    let login = hash(cleaned_email.clone());
+/// Save the language preferred by the browser, falling back on the known one
+pub fn update_locale(
+    connector: &Connector,
+    user_id: &Uuid,
+    user_locale: &str,
+    accept_language: Option<String>,
+) -> Result<Locale, Error> {
+    match accept_language
+        .as_deref()
+        .and_then(Locale::from_accept_language)
+    {
+        Some(locale) => {
+            if locale.as_str() != user_locale {
+                user::set_locale(connector, user_id, locale.as_str())?;
+            }
 
-    (login, cleaned_email)
+            Ok(locale)
+        }
+        None => Ok(Locale::from_code(user_locale).unwrap_or_default()),
+    }
 }
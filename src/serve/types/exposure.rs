@@ -0,0 +1,57 @@
+use crate::model::exposure::Exposure as ExposureModel;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureSummary {
+    pub exposures_count: usize,
+    /// Sum of overlaps in minutes with approved infections
+    pub cumulative_overlap_minutes: i64,
+    pub exposures: Vec<Exposure>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exposure {
+    pub checkin_id: Uuid,
+    pub start_timestamp: DateTime<Utc>,
+    pub end_timestamp: DateTime<Utc>,
+    pub place_id: Uuid,
+    pub place_name: String,
+    pub organization_name: String,
+    pub infections_count: i64,
+    pub overlap_minutes: Option<i64>,
+    /// Highest gauge of the place during the checkin
+    pub gauge: Option<i64>,
+}
+
+impl From<Vec<ExposureModel>> for ExposureSummary {
+    fn from(exposures: Vec<ExposureModel>) -> Self {
+        ExposureSummary {
+            exposures_count: exposures.len(),
+            cumulative_overlap_minutes: exposures
+                .iter()
+                .filter_map(|exposure| exposure.overlap_minutes)
+                .sum(),
+            exposures: exposures.into_iter().map(|e| e.into()).collect(),
+        }
+    }
+}
+
+impl From<ExposureModel> for Exposure {
+    fn from(exposure: ExposureModel) -> Self {
+        Exposure {
+            checkin_id: exposure.checkin_id,
+            start_timestamp: exposure.start_timestamp,
+            end_timestamp: exposure.end_timestamp,
+            place_id: exposure.place_id,
+            place_name: exposure.place_name,
+            organization_name: exposure.organization_name,
+            infections_count: exposure.infections_count,
+            overlap_minutes: exposure.overlap_minutes,
+            gauge: exposure.gauge,
+        }
+    }
+}
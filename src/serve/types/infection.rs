@@ -1,7 +1,13 @@
 use super::Organization;
-use crate::model::infection::Infection as InfectionModel;
+use crate::model::infection::{
+    Infection as InfectionModel, InfectionStatus as InfectionStatusModel,
+};
+use crate::model::notification::{
+    NotificationStats as NotificationStatsModel,
+    PlaceNotificationSummary as PlaceNotificationSummaryModel,
+};
 use crate::model::organization::Organization as OrganizationModel;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -13,6 +19,41 @@ pub struct InfectionForm {
     pub end_timestamp: DateTime<Utc>,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfectionReportForm {
+    pub symptom_onset_date: NaiveDate,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfectionReport {
+    /// Number of places visited during the contagious period
+    pub exposures_count: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InfectionStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfectionReviewQuery {
+    /// Status of infections to review, pending if None
+    pub status: Option<InfectionStatus>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfectionRetractionQuery {
+    /// Notify visitors not potentially infected anymore
+    pub notify: Option<bool>,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Infection {
@@ -21,16 +62,96 @@ pub struct Infection {
     pub places_ids: Vec<Uuid>,
     pub start_timestamp: DateTime<Utc>,
     pub end_timestamp: DateTime<Utc>,
+    pub status: InfectionStatus,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    /// Reported by a visitor, whose identity is not disclosed
+    pub self_reported: bool,
+    pub notifications: NotificationStats,
+}
+
+/// Infection with the visitors warned in each of its places
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfectionSummary {
+    pub infection: Infection,
+    pub places: Vec<InfectionPlaceSummary>,
 }
 
-impl From<(InfectionModel, OrganizationModel)> for Infection {
-    fn from((infection, organization): (InfectionModel, OrganizationModel)) -> Self {
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfectionPlaceSummary {
+    pub place_id: Uuid,
+    pub place_name: String,
+    pub visitors_count: i64,
+}
+
+/// Delivery status of notifications sent to visitors
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationStats {
+    pub pending: i64,
+    pub sent: i64,
+    pub failed: i64,
+}
+
+impl From<(InfectionModel, OrganizationModel, NotificationStatsModel)> for Infection {
+    fn from(
+        (infection, organization, notifications): (
+            InfectionModel,
+            OrganizationModel,
+            NotificationStatsModel,
+        ),
+    ) -> Self {
         Infection {
             id: infection.id,
             organization: organization.into(),
             places_ids: infection.places_ids,
             start_timestamp: infection.start_timestamp,
             end_timestamp: infection.end_timestamp,
+            status: infection.status.into(),
+            reviewed_at: infection.reviewed_at,
+            self_reported: infection.reported_by.is_some(),
+            notifications: notifications.into(),
+        }
+    }
+}
+
+impl From<PlaceNotificationSummaryModel> for InfectionPlaceSummary {
+    fn from(summary: PlaceNotificationSummaryModel) -> Self {
+        InfectionPlaceSummary {
+            place_id: summary.place_id,
+            place_name: summary.place_name,
+            visitors_count: summary.visitors_count,
+        }
+    }
+}
+
+impl From<NotificationStatsModel> for NotificationStats {
+    fn from(stats: NotificationStatsModel) -> Self {
+        NotificationStats {
+            pending: stats.pending,
+            sent: stats.sent,
+            failed: stats.failed,
+        }
+    }
+}
+
+impl From<InfectionStatusModel> for InfectionStatus {
+    fn from(status: InfectionStatusModel) -> Self {
+        match status {
+            InfectionStatusModel::Pending => InfectionStatus::Pending,
+            InfectionStatusModel::Approved => InfectionStatus::Approved,
+            InfectionStatusModel::Rejected => InfectionStatus::Rejected,
+        }
+    }
+}
+
+impl From<InfectionStatus> for InfectionStatusModel {
+    fn from(status: InfectionStatus) -> Self {
+        match status {
+            InfectionStatus::Pending => InfectionStatusModel::Pending,
+            InfectionStatus::Approved => InfectionStatusModel::Approved,
+            InfectionStatus::Rejected => InfectionStatusModel::Rejected,
         }
     }
 }
@@ -1,17 +1,27 @@
 mod checkin;
 mod context;
+mod exposure;
 mod health;
 mod infection;
+mod occupancy;
+mod opening_hour;
 mod organization;
 mod place;
+mod push;
 mod session;
+mod statistics;
 mod user;
 
 pub use checkin::*;
 pub use context::*;
+pub use exposure::*;
 pub use health::*;
 pub use infection::*;
+pub use occupancy::*;
+pub use opening_hour::*;
 pub use organization::*;
 pub use place::*;
+pub use push::*;
 pub use session::*;
+pub use statistics::*;
 pub use user::*;
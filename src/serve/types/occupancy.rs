@@ -0,0 +1,52 @@
+use crate::model::occupancy::{
+    Occupancy as OccupancyModel, OccupancyBucket as OccupancyBucketModel,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OccupancyBucket {
+    Hour,
+    Day,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OccupancyQuery {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub bucket: OccupancyBucket,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Occupancy {
+    pub start_timestamp: DateTime<Utc>,
+    pub end_timestamp: DateTime<Utc>,
+    pub visitors: i64,
+    pub peak_gauge: i64,
+    /// Average duration in minutes
+    pub average_duration: Option<f64>,
+}
+
+impl From<OccupancyBucket> for OccupancyBucketModel {
+    fn from(bucket: OccupancyBucket) -> Self {
+        match bucket {
+            OccupancyBucket::Hour => OccupancyBucketModel::Hour,
+            OccupancyBucket::Day => OccupancyBucketModel::Day,
+        }
+    }
+}
+
+impl From<OccupancyModel> for Occupancy {
+    fn from(occupancy: OccupancyModel) -> Self {
+        Occupancy {
+            start_timestamp: occupancy.start_timestamp,
+            end_timestamp: occupancy.end_timestamp,
+            visitors: occupancy.visitors,
+            peak_gauge: occupancy.peak_gauge,
+            average_duration: occupancy.average_duration,
+        }
+    }
+}
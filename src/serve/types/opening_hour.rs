@@ -0,0 +1,95 @@
+use crate::model::opening_hour::{
+    OpeningHourDate as OpeningHourDateModel, OpeningHourDay as OpeningHourDayModel,
+};
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningHours {
+    pub days: Vec<OpeningHourDay>,
+    pub dates: Vec<OpeningHourDate>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningHourDay {
+    /// ISO day of week, 1 is Monday and 7 is Sunday
+    pub day: i16,
+    pub opening_time: NaiveTime,
+    pub closure_time: NaiveTime,
+    pub evacuation_time: NaiveTime,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningHourDate {
+    pub date: NaiveDate,
+    pub opening_time: Option<NaiveTime>,
+    pub closure_time: Option<NaiveTime>,
+    pub evacuation_time: Option<NaiveTime>,
+    pub closed: bool,
+}
+
+#[derive(Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningHoursForm {
+    #[validate(length(max = 28))]
+    #[validate]
+    pub days: Vec<OpeningHourDayForm>,
+    #[validate(length(max = 366))]
+    pub dates: Vec<OpeningHourDateForm>,
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningHourDayForm {
+    /// ISO day of week, 1 is Monday and 7 is Sunday
+    #[validate(range(min = 1, max = 7))]
+    pub day: i16,
+    pub opening_time: NaiveTime,
+    pub closure_time: NaiveTime,
+    pub evacuation_time: NaiveTime,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningHourDateForm {
+    pub date: NaiveDate,
+    pub opening_time: Option<NaiveTime>,
+    pub closure_time: Option<NaiveTime>,
+    pub evacuation_time: Option<NaiveTime>,
+    pub closed: bool,
+}
+
+impl From<(Vec<OpeningHourDayModel>, Vec<OpeningHourDateModel>)> for OpeningHours {
+    fn from((days, dates): (Vec<OpeningHourDayModel>, Vec<OpeningHourDateModel>)) -> Self {
+        OpeningHours {
+            days: days.into_iter().map(|d| d.into()).collect(),
+            dates: dates.into_iter().map(|d| d.into()).collect(),
+        }
+    }
+}
+
+impl From<OpeningHourDayModel> for OpeningHourDay {
+    fn from(day: OpeningHourDayModel) -> Self {
+        OpeningHourDay {
+            day: day.day,
+            opening_time: day.opening_time,
+            closure_time: day.closure_time,
+            evacuation_time: day.evacuation_time,
+        }
+    }
+}
+
+impl From<OpeningHourDateModel> for OpeningHourDate {
+    fn from(date: OpeningHourDateModel) -> Self {
+        OpeningHourDate {
+            date: date.date,
+            opening_time: date.opening_time,
+            closure_time: date.closure_time,
+            evacuation_time: date.evacuation_time,
+            closed: date.closed,
+        }
+    }
+}
@@ -3,6 +3,7 @@ use crate::model::organization::Organization as OrganizationModel;
 use crate::model::place::{Place as PlaceModel, PlaceSearchResult as PlaceSearchResultModel};
 use crate::model::types::GaugeLevel as GaugeLevelModel;
 use crate::types::{Location, Pagination, PaginationQuery};
+use chrono::{DateTime, Utc};
 use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -39,9 +40,25 @@ pub struct OwnedPlace {
     pub current_gauge_percent: Option<i64>,
     pub current_gauge_level: GaugeLevel,
     pub timezone: Tz,
+    /// Percent of the maximum gauge from which the level is warning, global one if None
+    pub warning_percent: Option<i64>,
+    /// Percent of the maximum gauge from which the level is alert, global one if None
+    pub alert_percent: Option<i64>,
+    /// Minutes of presence during an infection to be potentially infected, global one if None
+    pub minimum_overlap: Option<i64>,
+    /// Region code used by health authorities to review infections
+    pub region: Option<String>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceGauge {
+    pub current_gauge: i64,
+    pub current_gauge_percent: Option<i64>,
+    pub current_gauge_level: GaugeLevel,
+}
+
+#[derive(Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum GaugeLevel {
     Unknown,
@@ -54,6 +71,9 @@ pub enum GaugeLevel {
 #[serde(rename_all = "camelCase")]
 pub struct PlaceSearchResult {
     pub meter_distance: f64,
+    /// Current or next opening, None when the place has no opening hours
+    pub opening_timestamp: Option<DateTime<Utc>>,
+    pub closing_timestamp: Option<DateTime<Utc>>,
     pub place: Place,
 }
 
@@ -71,6 +91,8 @@ pub struct PlaceSearchQuery {
     #[validate(range(min = 1, max = 1000000))]
     pub radius: i64,
     pub maximum_gauge_level: Option<GaugeLevel>,
+    pub open_now: Option<bool>,
+    pub open_at: Option<DateTime<Utc>>,
     pub pagination: PaginationQuery,
 }
 
@@ -92,6 +114,20 @@ pub struct PlaceForm {
     #[validate(range(min = 1, max = 1440))]
     pub maximum_duration: i64,
     pub timezone: Tz,
+    #[validate(range(min = 1, max = 100))]
+    pub warning_percent: Option<i64>,
+    #[validate(range(min = 1, max = 100))]
+    pub alert_percent: Option<i64>,
+    #[validate(range(min = 0, max = 720))]
+    pub minimum_overlap: Option<i64>,
+}
+
+/// Region of a place, only set by health authorities
+#[derive(Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceRegionForm {
+    #[validate(length(min = 1, max = 10))]
+    pub region: Option<String>,
 }
 
 impl From<(PlaceModel, OrganizationModel)> for Place {
@@ -110,6 +146,16 @@ impl From<(PlaceModel, OrganizationModel)> for Place {
     }
 }
 
+impl From<PlaceModel> for PlaceGauge {
+    fn from(place: PlaceModel) -> Self {
+        PlaceGauge {
+            current_gauge: place.current_gauge,
+            current_gauge_percent: place.current_gauge_percent,
+            current_gauge_level: place.current_gauge_level.into(),
+        }
+    }
+}
+
 impl From<GaugeLevelModel> for GaugeLevel {
     fn from(gauge_level: GaugeLevelModel) -> Self {
         match gauge_level {
@@ -137,6 +183,10 @@ impl From<(PlaceModel, OrganizationModel)> for OwnedPlace {
             current_gauge_percent: place.current_gauge_percent,
             current_gauge_level: place.current_gauge_level.into(),
             timezone: place.timezone.tz,
+            warning_percent: place.warning_percent,
+            alert_percent: place.alert_percent,
+            minimum_overlap: place.minimum_overlap,
+            region: place.region,
         }
     }
 }
@@ -145,6 +195,8 @@ impl From<PlaceSearchResultModel> for PlaceSearchResult {
     fn from(search_result: PlaceSearchResultModel) -> Self {
         PlaceSearchResult {
             meter_distance: search_result.meter_distance,
+            opening_timestamp: search_result.opening_timestamp,
+            closing_timestamp: search_result.closing_timestamp,
             place: (search_result.place, search_result.organization).into(),
         }
     }
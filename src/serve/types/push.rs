@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// Subscription as serialized by `PushSubscription.toJSON()` in browsers
+#[derive(Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct PushSubscriptionForm {
+    #[validate(url, length(max = 2048))]
+    pub endpoint: String,
+    #[validate]
+    pub keys: PushSubscriptionKeys,
+}
+
+#[derive(Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct PushSubscriptionKeys {
+    #[validate(length(min = 1, max = 256))]
+    pub p256dh: String,
+    #[validate(length(min = 1, max = 256))]
+    pub auth: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PushKey {
+    /// VAPID public key to use as `applicationServerKey`
+    pub public_key: String,
+}
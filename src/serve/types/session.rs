@@ -1,6 +1,7 @@
 use super::User;
 use crate::model::session::Session as SessionModel;
 use crate::model::user::UserRole;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -14,6 +15,7 @@ pub enum RedirectPage {
     CheckinConfirmation { place_id: Uuid },
     Checkins,
     Places,
+    Infections,
 }
 
 #[derive(Deserialize, Validate)]
@@ -49,6 +51,27 @@ pub struct Credentials {
     pub user: User,
 }
 
+/// Short-lived signed token, sent as `Authorization: Bearer <access_token>`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessToken {
+    pub access_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Device connected to the account of the user
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDescription {
+    pub id: Uuid,
+    /// User-Agent of the device at login
+    pub description: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Session used by the request
+    pub current: bool,
+}
+
 impl From<SessionModel> for Session {
     fn from(session: SessionModel) -> Self {
         Session {
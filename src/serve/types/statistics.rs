@@ -0,0 +1,73 @@
+use crate::model::statistics::{
+    InfectionStatistics as InfectionStatisticsModel, StatisticsGrouping as StatisticsGroupingModel,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StatisticsGrouping {
+    Day,
+    Place,
+    Cell,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsQuery {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub group_by: StatisticsGrouping,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfectionStatisticsSummary {
+    /// Minimum number of exposures of a published group
+    pub k_anonymity: i64,
+    /// Number of groups suppressed for having fewer exposures
+    pub suppressed_count: usize,
+    pub statistics: Vec<InfectionStatistics>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfectionStatistics {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell_longitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell_latitude: Option<f64>,
+    pub infections: i64,
+    pub exposures: i64,
+}
+
+impl From<StatisticsGrouping> for StatisticsGroupingModel {
+    fn from(grouping: StatisticsGrouping) -> Self {
+        match grouping {
+            StatisticsGrouping::Day => StatisticsGroupingModel::Day,
+            StatisticsGrouping::Place => StatisticsGroupingModel::Place,
+            StatisticsGrouping::Cell => StatisticsGroupingModel::Cell,
+        }
+    }
+}
+
+impl From<InfectionStatisticsModel> for InfectionStatistics {
+    fn from(statistics: InfectionStatisticsModel) -> Self {
+        InfectionStatistics {
+            day: statistics.day,
+            place_id: statistics.place_id,
+            place_name: statistics.place_name,
+            cell_longitude: statistics.cell_longitude,
+            cell_latitude: statistics.cell_latitude,
+            infections: statistics.infections,
+            exposures: statistics.exposures,
+        }
+    }
+}
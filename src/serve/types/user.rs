@@ -1,7 +1,7 @@
 use super::{Organization, Session};
 use crate::model::organization::Organization as OrganizationModel;
 use crate::model::user::{User as UserModel, UserRole};
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use uuid::Uuid;
 
 #[derive(Serialize)]
@@ -9,6 +9,7 @@ pub struct User {
     pub id: Uuid,
     pub role: UserRole,
     pub email: String,
+    pub locale: String,
 }
 
 pub struct PublicUser {
@@ -17,11 +18,15 @@ pub struct PublicUser {
 }
 
 pub struct ProfessionalUser {
-    pub user: User,
-    pub session: Session,
     pub organization: Organization,
 }
 
+pub struct HealthAuthorityUser {
+    pub user: User,
+    /// Region reviewed by the health authority, all regions if None
+    pub region: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct Profile {
     pub id: Uuid,
@@ -30,18 +35,13 @@ pub struct Profile {
     pub organization: Option<Organization>,
 }
 
-#[derive(Deserialize, Validate)]
-pub struct ProfileForm {
-    #[validate(email)]
-    pub email: String,
-}
-
 impl From<UserModel> for User {
     fn from(user: UserModel) -> Self {
         User {
             id: user.id,
             role: user.role,
             email: user.email,
+            locale: user.locale,
         }
     }
 }
@@ -0,0 +1,461 @@
+use crate::connector::email::template::{
+    InfectionReportEmail, InfectionRetractionEmail, InfectionSummaryEmail, InfectionSummaryPlace,
+    InfectionWarningEmail, InfectionWarningExposure, Locale,
+};
+use crate::connector::email::Error as EmailError;
+use crate::connector::push::{Error as PushError, PushMessage, Subscription};
+use crate::connector::Connector;
+use crate::model::error::Error;
+use crate::model::notification::{self, NotificationKind, PendingNotification};
+use crate::model::push_subscription::{self, PushSubscription};
+use crate::model::statistics::{self, StatisticsGrouping};
+use crate::model::{checkin, opening_hour, place, rate_limit, session, user};
+use crate::security::get_auth_from_email;
+use chrono::{Duration, Utc};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+#[derive(Clap, Clone, Copy, Debug)]
+pub enum TaskType {
+    /// Reconcile all gauges with active checkins, expiring ended ones, and report drift
+    #[clap(name = "refresh-all-gauges")]
+    RefreshAllGauges,
+
+    /// Delete gauge snapshots older than their retention
+    #[clap(name = "clean-gauge-snapshots")]
+    CleanGaugeSnapshots,
+
+    /// Compute opening and closing timestamps of the next days based on opening hours
+    #[clap(name = "compute-opening-hours")]
+    ComputeOpeningHours,
+
+    /// Close checkins still active after the evacuation of their place
+    #[clap(name = "leave-evacuated-checkins")]
+    LeaveEvacuatedCheckins,
+
+    /// Deliver pending notifications, retrying failed ones with a backoff
+    #[clap(name = "send-notifications")]
+    SendNotifications,
+
+    /// Remind visitors subscribed to push notifications that their checkin ends soon
+    #[clap(name = "send-checkin-reminders")]
+    SendCheckinReminders,
+
+    /// Disable expired sessions, delete checkins of sessions never confirmed and old rate limits
+    #[clap(name = "clean-expired-sessions")]
+    CleanExpiredSessions,
+
+    /// Derive logins of all users again from their email, after setting or changing LOGIN_PEPPER
+    #[clap(name = "upgrade-logins")]
+    UpgradeLogins,
+
+    /// Export anonymous infection statistics by day, place and grid cell as CSV files
+    #[clap(name = "export-statistics")]
+    ExportStatistics,
+}
+
+impl TaskType {
+    /// Key of the advisory lock preventing concurrent executions across replicas
+    pub fn lock_key(&self) -> i64 {
+        match self {
+            TaskType::RefreshAllGauges => 1,
+            TaskType::ComputeOpeningHours => 2,
+            TaskType::LeaveEvacuatedCheckins => 3,
+            TaskType::SendNotifications => 4,
+            TaskType::ExportStatistics => 5,
+            TaskType::SendCheckinReminders => 6,
+            TaskType::CleanExpiredSessions => 7,
+            TaskType::UpgradeLogins => 8,
+            TaskType::CleanGaugeSnapshots => 9,
+        }
+    }
+
+    pub async fn execute(&self, connector: &Connector) -> Result<(), Error> {
+        match self {
+            TaskType::RefreshAllGauges => place::refresh_all_gauges(connector).map(|changes| {
+                let mut drift: i64 = 0;
+
+                // Changes also include levels moved by edited percents, without any drift
+                for change in changes.iter() {
+                    let place_drift = change.previous_gauge - change.current_gauge;
+
+                    if place_drift != 0 {
+                        warn!(
+                            "Gauge of place {} drifted by {} from its {} active visitors",
+                            change.id, place_drift, change.current_gauge
+                        );
+                        drift += place_drift.abs();
+                    }
+                }
+
+                info!(
+                    "{} gauges reconciled with a drift of {}",
+                    changes.len(),
+                    drift
+                );
+            }),
+            TaskType::CleanGaugeSnapshots => place::delete_all_expired_gauge_snapshots(connector)
+                .map(|count| {
+                    info!("{} expired gauge snapshots deleted", count);
+                }),
+            TaskType::ComputeOpeningHours => {
+                opening_hour::refresh_all_computed(connector).map(|count| {
+                    info!("{} opening intervals computed", count);
+                })
+            }
+            TaskType::LeaveEvacuatedCheckins => {
+                checkin::leave_all_evacuated(connector).map(|count| {
+                    info!("{} evacuated checkins closed", count);
+                })
+            }
+            TaskType::SendNotifications => {
+                send_notifications(connector).await.map(|(sent, failed)| {
+                    info!("{} notifications sent, {} attempts failed", sent, failed);
+                })
+            }
+            TaskType::SendCheckinReminders => {
+                send_checkin_reminders(connector).await.map(|count| {
+                    info!("{} checkin reminders pushed", count);
+                })
+            }
+            TaskType::CleanExpiredSessions => {
+                clean_expired_sessions(connector).map(|(sessions, checkins)| {
+                    info!(
+                        "{} expired sessions disabled, {} unconfirmed checkins deleted",
+                        sessions, checkins
+                    );
+                })
+            }
+            TaskType::UpgradeLogins => upgrade_logins(connector).map(|(upgraded, failed)| {
+                info!("{} logins upgraded, {} failed", upgraded, failed);
+            }),
+            TaskType::ExportStatistics => export_statistics(connector).map(|suppressed| {
+                info!("Statistics exported, {} groups suppressed", suppressed);
+            }),
+        }
+    }
+}
+
+/// Send due notifications by batches until none is left
+///
+/// Warnings of a same user are consolidated in a single email.
+async fn send_notifications(connector: &Connector) -> Result<(usize, usize), Error> {
+    let mut sent = 0;
+    let mut failed = 0;
+
+    loop {
+        let notifications = notification::get_all_due(connector)?;
+
+        if notifications.is_empty() {
+            break;
+        }
+
+        let (warnings, others): (Vec<PendingNotification>, Vec<PendingNotification>) =
+            notifications
+                .into_iter()
+                .partition(|n| n.kind == NotificationKind::InfectionWarning);
+
+        // Group warnings by user
+        let mut warnings_by_user: BTreeMap<Uuid, Vec<PendingNotification>> = BTreeMap::new();
+
+        for warning in warnings {
+            warnings_by_user
+                .entry(warning.user_id)
+                .or_default()
+                .push(warning);
+        }
+
+        for (_, warnings) in warnings_by_user {
+            let result = connector
+                .email
+                .send_one(&InfectionWarningEmail {
+                    to: warnings[0].email.clone(),
+                    locale: Locale::from_code(&warnings[0].locale).unwrap_or_default(),
+                    exposures: warnings
+                        .iter()
+                        .map(|warning| InfectionWarningExposure {
+                            organization_name: warning.organization_name.clone(),
+                            place_name: warning.place_name.clone(),
+                            checkin_datetime: warning.checkin_start_timestamp,
+                            timezone: warning.timezone.tz,
+                            overlap_minutes: warning.overlap_minutes.unwrap_or_default(),
+                        })
+                        .collect(),
+                })
+                .await;
+
+            let ids: Vec<Uuid> = warnings.iter().map(|warning| warning.id).collect();
+
+            if set_delivery(connector, &ids, result)? {
+                sent += ids.len();
+            } else {
+                failed += ids.len();
+            }
+
+            // Push only once, not on retries of the email, failures are only logged
+            // since the email delivery is already recorded
+            if connector.push.is_enabled() && warnings.iter().all(|w| w.attempts == 0) {
+                let pushed =
+                    match push_subscription::get_all_with_user(connector, &warnings[0].user_id) {
+                        Ok(subscriptions) => {
+                            push_to_all(
+                                connector,
+                                subscriptions,
+                                &PushMessage::InfectionWarning {
+                                    exposures_count: warnings.len(),
+                                },
+                            )
+                            .await
+                        }
+                        Err(error) => Err(error),
+                    };
+
+                if let Err(error) = pushed {
+                    error!("Unable to push warnings: {}", error);
+                }
+            }
+        }
+
+        for notification in others {
+            let locale = Locale::from_code(&notification.locale).unwrap_or_default();
+
+            let result = match notification.kind {
+                NotificationKind::InfectionRetraction => {
+                    connector
+                        .email
+                        .send_one(&InfectionRetractionEmail {
+                            to: notification.email.clone(),
+                            organization_name: notification.organization_name.clone(),
+                            place_name: notification.place_name.clone(),
+                            checkin_datetime: notification.checkin_start_timestamp,
+                            timezone: notification.timezone.tz,
+                            locale,
+                        })
+                        .await
+                }
+                NotificationKind::InfectionReport => {
+                    connector
+                        .email
+                        .send_one(&InfectionReportEmail {
+                            to: notification.email.clone(),
+                            place_name: notification.place_name.clone(),
+                            checkin_date: notification.checkin_start_timestamp,
+                            timezone: notification.timezone.tz,
+                            locale,
+                        })
+                        .await
+                }
+                NotificationKind::InfectionWarning | NotificationKind::InfectionSummary => continue,
+            };
+
+            if set_delivery(connector, &vec![notification.id], result)? {
+                sent += 1;
+            } else {
+                failed += 1;
+            }
+        }
+    }
+
+    // Summaries are sent once the warnings of their infection have been sent
+    loop {
+        let summaries = notification::get_all_due_summaries(connector)?;
+
+        if summaries.is_empty() {
+            return Ok((sent, failed));
+        }
+
+        for summary in summaries {
+            let places =
+                notification::get_summary_with_infection(connector, &summary.infection_id)?;
+
+            let result = connector
+                .email
+                .send_one(&InfectionSummaryEmail {
+                    to: summary.email.clone(),
+                    start_datetime: summary.start_timestamp,
+                    end_datetime: summary.end_timestamp,
+                    timezone: summary.timezone.tz,
+                    places: places
+                        .into_iter()
+                        .map(|place| InfectionSummaryPlace {
+                            place_name: place.place_name,
+                            visitors_count: place.visitors_count,
+                        })
+                        .collect(),
+                    locale: Locale::from_code(&summary.locale).unwrap_or_default(),
+                })
+                .await;
+
+            if set_delivery(connector, &vec![summary.id], result)? {
+                sent += 1;
+            } else {
+                failed += 1;
+            }
+        }
+    }
+}
+
+/// Record the delivery result of notifications, returning whether they were sent
+fn set_delivery(
+    connector: &Connector,
+    ids: &Vec<Uuid>,
+    result: Result<(), EmailError>,
+) -> Result<bool, Error> {
+    match result {
+        Ok(_) => notification::set_sent(connector, ids).map(|_| true),
+        Err(error) => {
+            notification::set_attempt_failed(connector, ids, &error.to_string()).map(|_| false)
+        }
+    }
+}
+
+/// Push a message to all subscriptions, deleting expired ones, and return the number delivered
+async fn push_to_all(
+    connector: &Connector,
+    subscriptions: Vec<PushSubscription>,
+    message: &PushMessage,
+) -> Result<usize, Error> {
+    let mut delivered = 0;
+
+    for subscription in subscriptions {
+        let result = connector
+            .push
+            .send(
+                &Subscription {
+                    endpoint: &subscription.endpoint,
+                    p256dh: &subscription.p256dh,
+                    auth: &subscription.auth,
+                },
+                message,
+            )
+            .await;
+
+        match result {
+            Ok(_) => delivered += 1,
+            Err(PushError::Expired) => push_subscription::delete(connector, &subscription.id)?,
+            Err(error) => error!("{}", error),
+        }
+    }
+
+    Ok(delivered)
+}
+
+/// Push a reminder to the sessions of checkins ending soon
+async fn send_checkin_reminders(connector: &Connector) -> Result<usize, Error> {
+    if !connector.push.is_enabled() {
+        return Ok(0);
+    }
+
+    let reminders = checkin::set_all_reminded(connector)?;
+
+    let sessions_ids: Vec<Uuid> = reminders.iter().map(|r| r.session_id).collect();
+    let mut subscriptions = push_subscription::get_all_with_sessions(connector, &sessions_ids)?;
+    let mut delivered = 0;
+
+    for reminder in reminders {
+        let (session_subscriptions, others) = subscriptions
+            .into_iter()
+            .partition(|s| s.session_id == reminder.session_id);
+        subscriptions = others;
+
+        delivered += push_to_all(
+            connector,
+            session_subscriptions,
+            &PushMessage::CheckinReminder {
+                checkin_id: reminder.id,
+                place_name: reminder.place_name,
+                end_timestamp: reminder.end_timestamp,
+            },
+        )
+        .await?;
+    }
+
+    Ok(delivered)
+}
+
+fn clean_expired_sessions(connector: &Connector) -> Result<(usize, usize), Error> {
+    let sessions = session::set_all_expired_disabled(connector)?;
+    push_subscription::delete_all_disabled(connector)?;
+    rate_limit::delete_all_expired(connector)?;
+    let checkins = checkin::delete_all_unconfirmed_expired(connector)?;
+
+    Ok((sessions, checkins))
+}
+
+fn upgrade_logins(connector: &Connector) -> Result<(usize, usize), Error> {
+    let mut upgraded = 0;
+    let mut failed = 0;
+
+    for user in user::get_all(connector)? {
+        let (login, _) =
+            get_auth_from_email(&connector.configuration.security.login_pepper, user.email);
+
+        if login == user.login {
+            continue;
+        }
+
+        // Fails if the user signed up again with a new pepper before the upgrade
+        match user::set_login(connector, &user.id, &login) {
+            Ok(_) => upgraded += 1,
+            Err(error) => {
+                error!("Unable to upgrade login of user {}: {}", user.id, error);
+                failed += 1;
+            }
+        }
+    }
+
+    Ok((upgraded, failed))
+}
+
+/// Write one CSV file of aggregated statistics per grouping over the last days
+fn export_statistics(connector: &Connector) -> Result<usize, Error> {
+    let configuration = &connector.configuration.statistics;
+    let to = Utc::now();
+    let from = to - Duration::days(configuration.export_days);
+    let mut suppressed_count = 0;
+
+    for (grouping, file_name) in [
+        (StatisticsGrouping::Day, "infection_statistics_day.csv"),
+        (StatisticsGrouping::Place, "infection_statistics_place.csv"),
+        (StatisticsGrouping::Cell, "infection_statistics_cell.csv"),
+    ]
+    .iter()
+    {
+        let (aggregated, suppressed) =
+            statistics::get_all_aggregated(connector, *grouping, None, &from, &to)?;
+        suppressed_count += suppressed;
+
+        let mut writer =
+            csv::Writer::from_path(Path::new(&configuration.export_directory).join(file_name))?;
+
+        writer.write_record([
+            "day",
+            "place_id",
+            "place_name",
+            "cell_longitude",
+            "cell_latitude",
+            "infections",
+            "exposures",
+        ])?;
+
+        for row in aggregated {
+            writer.write_record([
+                row.day.map(|d| d.to_string()).unwrap_or_default(),
+                row.place_id.map(|id| id.to_string()).unwrap_or_default(),
+                row.place_name.unwrap_or_default(),
+                row.cell_longitude
+                    .map(|l| l.to_string())
+                    .unwrap_or_default(),
+                row.cell_latitude.map(|l| l.to_string()).unwrap_or_default(),
+                row.infections.to_string(),
+                row.exposures.to_string(),
+            ])?;
+        }
+
+        writer.flush().map_err(csv::Error::from)?;
+    }
+
+    Ok(suppressed_count)
+}